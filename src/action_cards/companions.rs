@@ -0,0 +1,52 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::cards::{ActionCard, SupportCard};
+use crate::cost::Cost;
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::operation_context::OperationContext;
+
+// Creates two omni dice when the round starts, for two rounds
+struct Paimon {}
+
+struct PaimonSupport {
+    usages: AtomicUsize,
+}
+
+impl SupportCard for PaimonSupport {
+    fn name(&self) -> &'static str {
+        "Paimon"
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+
+    fn on_turn_start(&self, subject_player: usize, env: &mut GameEnvironment) {
+        let player = &mut env.players[subject_player];
+        player.dice_set.add_dice(ElementType::Universal);
+        player.dice_set.add_dice(ElementType::Universal);
+
+        let player_elements = player.get_character_elements();
+        player.dice_set.sort_dice(player_elements);
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ActionCard for Paimon {
+    fn name(&self) -> &'static str {
+        "Paimon"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::same(3)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.add_support(info.subject_player, Arc::new(PaimonSupport { usages: AtomicUsize::new(2) }));
+    }
+}
+
+pub fn paimon() -> Arc<dyn ActionCard> {
+    Arc::new(Paimon {})
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::cards::ActionCard;
+use crate::cost::{Cost, CostModifier, CostSubject};
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::operation_context::OperationContext;
+use crate::status::{Status, StatusCounter, StatusOwner};
+
+// Creates two omni dice
+struct BestestTravelCompanion {}
+
+impl ActionCard for BestestTravelCompanion {
+    fn name(&self) -> &'static str {
+        "The Bestest Travel Companion!"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::unaligned(2)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let player = &mut env.players[info.subject_player];
+        player.dice_set.add_dice(ElementType::Universal);
+        player.dice_set.add_dice(ElementType::Universal);
+
+        let player_elements = player.get_character_elements();
+        player.dice_set.sort_dice(player_elements);
+    }
+}
+
+// The next switch costs one die less
+struct ChangingShifts {}
+
+struct SwitchDiscount {
+    usages: AtomicUsize,
+}
+
+impl CostModifier for SwitchDiscount {
+    fn modify(&self, subject: CostSubject, cost: &mut Cost) -> bool {
+        if subject != CostSubject::ChangeActive || cost.dice_count() == 0 {
+            return false;
+        }
+
+        cost.reduce_dice(1);
+        true
+    }
+
+    fn consume(&self) {
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+}
+
+impl ActionCard for ChangingShifts {
+    fn name(&self) -> &'static str {
+        "Changing Shifts"
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].cost_modifiers.push(Arc::new(SwitchDiscount {
+            usages: AtomicUsize::new(1),
+        }));
+    }
+}
+
+// Moves one energy from each standby character to the active character
+struct CalxsArts {}
+
+impl ActionCard for CalxsArts {
+    fn name(&self) -> &'static str {
+        "Calx's Arts"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::same(1)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let player = &mut env.players[info.subject_player];
+        let active_character = player.active_character;
+
+        for i in 0..player.characters.len() {
+            let active = &player.characters[active_character];
+            if i == active_character || active.energy == active.max_energy {
+                continue;
+            }
+
+            if player.characters[i].energy > 0 && !player.characters[i].defeated {
+                player.characters[i].energy -= 1;
+                player.characters[active_character].gain_energy(1);
+            }
+        }
+
+        env.update_energy(info.subject_player);
+    }
+}
+
+// Removes every summon on the field
+struct GuardiansOath {}
+
+impl ActionCard for GuardiansOath {
+    fn name(&self) -> &'static str {
+        "Guardian's Oath"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::same(4)
+    }
+
+    fn use_card(&self, _info: OperationContext, env: &mut GameEnvironment) {
+        for player in env.players.iter_mut() {
+            while player.summoned_area_count > 0 {
+                player.remove_summoned(0);
+            }
+        }
+    }
+}
+
+// Draws two cards
+struct Strategize {}
+
+impl ActionCard for Strategize {
+    fn name(&self) -> &'static str {
+        "Strategize"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::same(1)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].draw_cards(2);
+    }
+}
+
+// The next switch is a fast action
+struct LeaveItToMe {}
+
+struct FastSwitch {
+    counter: StatusCounter,
+}
+
+impl Status for FastSwitch {
+    fn name(&self) -> &'static str {
+        "Leave It to Me!"
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn fast_switch(&self, _owner: StatusOwner) -> bool {
+        true
+    }
+}
+
+impl ActionCard for LeaveItToMe {
+    fn name(&self) -> &'static str {
+        "Leave It to Me!"
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].add_combat_status(Arc::new(FastSwitch {
+            counter: StatusCounter::new(1, StatusCounter::UNLIMITED),
+        }));
+    }
+}
+
+pub fn bestest_travel_companion() -> Arc<dyn ActionCard> {
+    Arc::new(BestestTravelCompanion {})
+}
+
+pub fn changing_shifts() -> Arc<dyn ActionCard> {
+    Arc::new(ChangingShifts {})
+}
+
+pub fn calxs_arts() -> Arc<dyn ActionCard> {
+    Arc::new(CalxsArts {})
+}
+
+pub fn guardians_oath() -> Arc<dyn ActionCard> {
+    Arc::new(GuardiansOath {})
+}
+
+pub fn strategize() -> Arc<dyn ActionCard> {
+    Arc::new(Strategize {})
+}
+
+pub fn leave_it_to_me() -> Arc<dyn ActionCard> {
+    Arc::new(LeaveItToMe {})
+}
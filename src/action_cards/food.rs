@@ -0,0 +1,241 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::cards::{ActionCard, CardKind};
+use crate::cost::{Cost, CostModifier, CostSubject};
+use crate::damage::{DamageEvent, DamageKind, ModifierStage};
+use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::operation_context::OperationContext;
+use crate::status::{Status, StatusCounter, StatusOwner};
+
+// Food can only be eaten by characters that are still alive
+fn can_eat(info: &OperationContext, env: &GameEnvironment) -> bool {
+    !env.players[info.subject_player].characters[info.subject_character].defeated
+}
+
+// Heals the target character
+struct Food {
+    name: &'static str,
+    cost: Cost,
+    heal: usize,
+}
+
+impl ActionCard for Food {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Food
+    }
+
+    fn cost(&self) -> Cost {
+        self.cost.clone()
+    }
+
+    fn can_use(&self, info: &OperationContext, env: &GameEnvironment) -> bool {
+        can_eat(info, env)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].characters[info.subject_character].heal(self.heal);
+    }
+}
+
+// Changes the next damage dealt or taken by the character this round
+struct FoodBuff {
+    name: &'static str,
+    stage: ModifierStage,
+    amount: usize,
+    // Damage kinds the buff applies to, incoming damage if empty
+    kinds: Vec<DamageKind>,
+    counter: StatusCounter,
+}
+
+impl Status for FoodBuff {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn stage(&self) -> ModifierStage {
+        self.stage
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        if self.expired() {
+            return;
+        }
+
+        if self.kinds.is_empty() {
+            if event.target.player == owner.player && Some(event.target.character) == owner.character {
+                event.amount -= self.amount.min(event.amount);
+                self.counter.consume();
+            }
+        } else if event.source.player == owner.player && event.source.character == owner.character
+            && self.kinds.contains(&event.kind) {
+            event.amount += self.amount;
+            self.counter.consume();
+        }
+    }
+}
+
+struct DamageFood {
+    name: &'static str,
+    cost: Cost,
+    stage: ModifierStage,
+    amount: usize,
+    kinds: Vec<DamageKind>,
+}
+
+impl ActionCard for DamageFood {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Food
+    }
+
+    fn cost(&self) -> Cost {
+        self.cost.clone()
+    }
+
+    fn can_use(&self, info: &OperationContext, env: &GameEnvironment) -> bool {
+        can_eat(info, env)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].characters[info.subject_character].add_status(Arc::new(FoodBuff {
+            name: self.name,
+            stage: self.stage,
+            amount: self.amount,
+            kinds: self.kinds.clone(),
+            counter: StatusCounter::new(1, 1),
+        }));
+    }
+}
+
+// The next normal attack of the character costs one unaligned die less
+struct NorthernSmokedChicken {}
+
+struct NormalAttackDiscount {
+    character: usize,
+    usages: AtomicUsize,
+}
+
+impl CostModifier for NormalAttackDiscount {
+    fn modify(&self, subject: CostSubject, cost: &mut Cost) -> bool {
+        if subject != CostSubject::Skill(self.character, SkillType::NormalAttack) || cost.unaligned == 0 {
+            return false;
+        }
+
+        cost.unaligned -= 1;
+        true
+    }
+
+    fn consume(&self) {
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+}
+
+impl ActionCard for NorthernSmokedChicken {
+    fn name(&self) -> &'static str {
+        "Northern Smoked Chicken"
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Food
+    }
+
+    fn can_use(&self, info: &OperationContext, env: &GameEnvironment) -> bool {
+        can_eat(info, env)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        env.players[info.subject_player].cost_modifiers.push(Arc::new(NormalAttackDiscount {
+            character: info.subject_character,
+            usages: AtomicUsize::new(1),
+        }));
+    }
+}
+
+// Revives a defeated character with one hp
+struct TeyvatFriedEgg {}
+
+impl ActionCard for TeyvatFriedEgg {
+    fn name(&self) -> &'static str {
+        "Teyvat Fried Egg"
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Food
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::same(3)
+    }
+
+    fn can_use(&self, info: &OperationContext, env: &GameEnvironment) -> bool {
+        env.players[info.subject_player].characters[info.subject_character].defeated
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let character = &mut env.players[info.subject_player].characters[info.subject_character];
+        character.defeated = false;
+        character.hp = 1;
+    }
+}
+
+pub fn sweet_madame() -> Arc<dyn ActionCard> {
+    Arc::new(Food { name: "Sweet Madame", cost: Cost::default(), heal: 1 })
+}
+
+pub fn mondstadt_hash_brown() -> Arc<dyn ActionCard> {
+    Arc::new(Food { name: "Mondstadt Hash Brown", cost: Cost::same(1), heal: 2 })
+}
+
+pub fn lotus_flower_crisp() -> Arc<dyn ActionCard> {
+    Arc::new(DamageFood {
+        name: "Lotus Flower Crisp",
+        cost: Cost::same(1),
+        stage: ModifierStage::Reduction,
+        amount: 3,
+        kinds: vec![],
+    })
+}
+
+pub fn adeptus_temptation() -> Arc<dyn ActionCard> {
+    Arc::new(DamageFood {
+        name: "Adeptus' Temptation",
+        cost: Cost::unaligned(2),
+        stage: ModifierStage::Additive,
+        amount: 3,
+        kinds: vec![DamageKind::ElementalSkill, DamageKind::ElementalBurst],
+    })
+}
+
+pub fn jueyun_guoba() -> Arc<dyn ActionCard> {
+    Arc::new(DamageFood {
+        name: "Jueyun Guoba",
+        cost: Cost::default(),
+        stage: ModifierStage::Additive,
+        amount: 1,
+        kinds: vec![DamageKind::NormalAttack],
+    })
+}
+
+pub fn northern_smoked_chicken() -> Arc<dyn ActionCard> {
+    Arc::new(NorthernSmokedChicken {})
+}
+
+pub fn teyvat_fried_egg() -> Arc<dyn ActionCard> {
+    Arc::new(TeyvatFriedEgg {})
+}
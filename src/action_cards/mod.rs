@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use crate::cards::ActionCard;
+
+pub mod starsigns;
+pub mod events;
+pub mod companions;
+pub mod food;
+pub mod resonance;
+pub mod talent;
+
+// Every action card that can be put in a deck
+pub fn all_cards() -> Vec<Arc<dyn ActionCard>> {
+    vec![
+        starsigns::starsigns(),
+        events::bestest_travel_companion(),
+        events::changing_shifts(),
+        events::calxs_arts(),
+        events::guardians_oath(),
+        events::strategize(),
+        events::leave_it_to_me(),
+        companions::paimon(),
+        food::sweet_madame(),
+        food::mondstadt_hash_brown(),
+        food::lotus_flower_crisp(),
+        food::adeptus_temptation(),
+        food::jueyun_guoba(),
+        food::northern_smoked_chicken(),
+        food::teyvat_fried_egg(),
+        resonance::woven_flames(),
+        resonance::woven_thunder(),
+        resonance::woven_ice(),
+        resonance::high_voltage(),
+        talent::naganohara_meteor_swarm(),
+        talent::stellar_predator(),
+        talent::undivided_heart(),
+    ]
+}
+
+pub fn create_card(name: &str) -> Option<Arc<dyn ActionCard>> {
+    all_cards().into_iter().find(|a| a.name() == name)
+}
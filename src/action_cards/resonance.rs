@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use crate::cards::{ActionCard, CardKind};
+use crate::cost::Cost;
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::operation_context::OperationContext;
+
+// Creates one die of the element
+struct Woven {
+    name: &'static str,
+    element: ElementType,
+}
+
+impl ActionCard for Woven {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Resonance(self.element)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let player = &mut env.players[info.subject_player];
+        player.dice_set.add_dice(self.element);
+
+        let player_elements = player.get_character_elements();
+        player.dice_set.sort_dice(player_elements);
+    }
+}
+
+// A character without full energy gains one energy
+struct HighVoltage {}
+
+impl ActionCard for HighVoltage {
+    fn name(&self) -> &'static str {
+        "Elemental Resonance: High Voltage"
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Resonance(ElementType::Electro)
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::elemental(ElementType::Electro, 1)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let player = &mut env.players[info.subject_player];
+        let active_character = player.active_character;
+
+        // The active character is charged first
+        let order = std::iter::once(active_character).chain(0..player.characters.len());
+        for i in order {
+            let character = &mut player.characters[i];
+            if !character.defeated && character.energy < character.max_energy {
+                character.gain_energy(1);
+                break;
+            }
+        }
+
+        env.update_energy(info.subject_player);
+    }
+}
+
+pub fn woven_flames() -> Arc<dyn ActionCard> {
+    Arc::new(Woven { name: "Elemental Resonance: Woven Flames", element: ElementType::Pyro })
+}
+
+pub fn woven_thunder() -> Arc<dyn ActionCard> {
+    Arc::new(Woven { name: "Elemental Resonance: Woven Thunder", element: ElementType::Electro })
+}
+
+pub fn woven_ice() -> Arc<dyn ActionCard> {
+    Arc::new(Woven { name: "Elemental Resonance: Woven Ice", element: ElementType::Cryo })
+}
+
+pub fn high_voltage() -> Arc<dyn ActionCard> {
+    Arc::new(HighVoltage {})
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use crate::cards::ActionCard;
+use crate::cost::Cost;
+use crate::game_environment::GameEnvironment;
+use crate::operation_context::OperationContext;
+
+// The active character gains one energy
+struct Starsigns {}
+
+impl ActionCard for Starsigns {
+    fn name(&self) -> &'static str {
+        "Starsigns"
+    }
+
+    fn cost(&self) -> Cost {
+        Cost::unaligned(2)
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let player = &mut env.players[info.subject_player];
+        let active_character = player.active_character;
+        player.characters[active_character].gain_energy(1);
+
+        env.update_energy(info.subject_player);
+    }
+}
+
+pub fn starsigns() -> Arc<dyn ActionCard> {
+    Arc::new(Starsigns {})
+}
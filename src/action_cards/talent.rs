@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use crate::cards::{ActionCard, CardKind};
+use crate::cost::Cost;
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::operation_context::OperationContext;
+
+// Played by the character it belongs to, the character uses the skill when played
+struct Talent {
+    name: &'static str,
+    character: &'static str,
+    skill: SkillType,
+    cost: Cost,
+}
+
+impl ActionCard for Talent {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn kind(&self) -> CardKind {
+        CardKind::Talent(self.character, self.skill)
+    }
+
+    fn cost(&self) -> Cost {
+        self.cost.clone()
+    }
+
+    // The character has to be active and able to use the skill
+    fn can_use(&self, info: &OperationContext, env: &GameEnvironment) -> bool {
+        let player = &env.players[info.subject_player];
+        let character = &player.characters[player.active_character];
+        let (cost, _) = env.skill_cost(info.subject_player, self.skill);
+        character.name == self.character && !character.is_frozen() && character.energy >= cost.energy
+    }
+
+    fn is_combat_action(&self) -> bool {
+        true
+    }
+
+    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {
+        let (cost, _) = env.skill_cost(info.subject_player, self.skill);
+        env.use_skill(info.subject_player, self.skill, cost.energy);
+    }
+}
+
+pub fn naganohara_meteor_swarm() -> Arc<dyn ActionCard> {
+    Arc::new(Talent {
+        name: "Naganohara Meteor Swarm",
+        character: "Yoimiya",
+        skill: SkillType::ESkill,
+        cost: Cost::elemental(ElementType::Pyro, 2),
+    })
+}
+
+pub fn stellar_predator() -> Arc<dyn ActionCard> {
+    Arc::new(Talent {
+        name: "Stellar Predator",
+        character: "Fischl",
+        skill: SkillType::ESkill,
+        cost: Cost::elemental(ElementType::Electro, 3),
+    })
+}
+
+pub fn undivided_heart() -> Arc<dyn ActionCard> {
+    Arc::new(Talent {
+        name: "Undivided Heart",
+        character: "Ganyu",
+        skill: SkillType::NormalAttack,
+        cost: Cost::elemental(ElementType::Cryo, 5),
+    })
+}
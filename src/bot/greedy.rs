@@ -0,0 +1,38 @@
+use crate::bot::Bot;
+use crate::game_environment::GameEnvironment;
+use crate::game_events::GameEvent;
+
+// Attacks with the skill dealing the most damage whenever it can
+pub struct GreedyBot;
+
+impl GreedyBot {
+    // Higher is better
+    fn score(env: &GameEnvironment, index: usize, action: &GameEvent) -> isize {
+        let player = &env.players[index];
+        match action {
+            GameEvent::UseSkill(_, skill, _) => {
+                let damage = player.characters[player.active_character].skill_damage(*skill) as isize;
+                // Skills without damage are not worth the dice
+                if damage == 0 { -1 } else { 100 + damage }
+            }
+
+            // Keeps dice of the active character's element and rerolls the rest
+            GameEvent::RerollDice(_, dices) => dices.len() as isize,
+            GameEvent::ChooseActive(_, character) => player.characters[*character].hp as isize,
+            GameEvent::DeclareRoundEnd(_) => 0,
+            // Every other action costs dice or cards without a known benefit
+            _ => -2,
+        }
+    }
+}
+
+impl Bot for GreedyBot {
+    fn name(&self) -> &'static str {
+        "Greedy"
+    }
+
+    fn choose_action(&mut self, env: &GameEnvironment, index: usize, actions: Vec<GameEvent>) -> GameEvent {
+        // The last of equally good actions is taken, so bursts win over weaker skills with the same damage
+        actions.into_iter().max_by_key(|a| GreedyBot::score(env, index, a)).unwrap()
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::mpsc::Sender;
+use serde::Deserialize;
+use crate::game_environment::GameEnvironment;
+use crate::game_events::{GameEvent, SkillType};
+use crate::player::Player;
+
+pub mod random;
+pub mod greedy;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Deserialize)]
+pub enum BotKind {
+    Random,
+    Greedy,
+}
+
+// Plays a player slot of a room in place of a session
+pub trait Bot: Send + Sync {
+    fn name(&self) -> &'static str;
+    // Picks one of the legal actions of the player, there is always at least one
+    fn choose_action(&mut self, env: &GameEnvironment, index: usize, actions: Vec<GameEvent>) -> GameEvent;
+}
+
+pub fn create_bot(kind: BotKind) -> Box<dyn Bot> {
+    match kind {
+        BotKind::Random => Box::new(random::RandomBot::new()),
+        BotKind::Greedy => Box::new(greedy::GreedyBot),
+    }
+}
+
+impl GameEnvironment {
+    // Events the player could send now that pass validation, dice are selected like for clients sending none
+    pub fn legal_actions(&self, index: usize) -> Vec<GameEvent> {
+        let player = &self.players[index];
+        let characters = 0..player.characters.len();
+        let cards = 0..player.hand.len();
+        let element = player.characters[player.active_character].element;
+        let off_element: Vec<usize> = (0..player.dice_set.dice_count)
+            .filter(|i| !player.dice_set.matches(*i, element))
+            .collect();
+
+        let mut candidates = vec![
+            GameEvent::SwapCards(index, vec![]),
+            GameEvent::RerollDice(index, vec![]),
+            GameEvent::DeclareRoundEnd(index),
+        ];
+
+        if !off_element.is_empty() {
+            // Dice are sorted, the last one is the least useful to keep
+            for card in cards.clone() {
+                candidates.push(GameEvent::Tune(index, card, *off_element.last().unwrap()));
+            }
+            candidates.push(GameEvent::RerollDice(index, off_element));
+        }
+
+        for skill in [SkillType::NormalAttack, SkillType::ESkill, SkillType::QSkill] {
+            candidates.push(GameEvent::UseSkill(index, skill, vec![]));
+        }
+
+        for character in characters.clone() {
+            candidates.push(GameEvent::ChooseActive(index, character));
+            candidates.push(GameEvent::ChangeActive(index, character, vec![]));
+        }
+
+        for card in cards {
+            for target in characters.clone() {
+                candidates.push(GameEvent::UseActionCard(index, card, target, vec![]));
+            }
+        }
+
+        candidates.iter()
+            .map(|a| self.complete_dice_selection(a))
+            .filter(|a| self.validate(a).is_ok())
+            .collect()
+    }
+
+    pub fn add_bot(&mut self, bot: Box<dyn Bot>, player: Player) -> usize {
+        let index = self.add_player(None, player);
+        println!("{} bot joined as player {}", bot.name(), index);
+        self.bots[index] = Some(bot);
+        index
+    }
+
+    // Lets every bot the game is waiting for act, called on every tick
+    pub fn run_bots(&mut self, send: &Sender<GameEvent>) {
+        if self.result.is_some() {
+            return;
+        }
+
+        for index in 0..2usize {
+            // Taken out while choosing so the bot can read the environment
+            let Some(mut bot) = self.bots[index].take() else {
+                continue;
+            };
+
+            let actions = self.legal_actions(index);
+            if !actions.is_empty() {
+                let action = bot.choose_action(self, index, actions);
+                send.send(action).expect("Game loop has stopped");
+            }
+            self.bots[index] = Some(bot);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::bot::Bot;
+use crate::game_environment::GameEnvironment;
+use crate::game_events::GameEvent;
+
+// Plays any legal action, useful for testing the rules
+pub struct RandomBot {
+    // Separate from the game's generator, the chosen events are recorded anyway
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new() -> Self {
+        RandomBot {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Bot for RandomBot {
+    fn name(&self) -> &'static str {
+        "Random"
+    }
+
+    fn choose_action(&mut self, _env: &GameEnvironment, _index: usize, mut actions: Vec<GameEvent>) -> GameEvent {
+        let choice = self.rng.gen_range(0..actions.len());
+        actions.swap_remove(choice)
+    }
+}
@@ -1,6 +1,16 @@
 use std::sync::Arc;
+use crate::action_cards::create_card;
 use crate::cards::ActionCard;
 
-pub struct CardSet{
-    pub cards: [Arc<dyn ActionCard>; 10]
-}
\ No newline at end of file
+// Action cards in a player's deck
+pub struct CardSet {
+    pub cards: Vec<Arc<dyn ActionCard>>,
+}
+
+impl CardSet {
+    // Returns None if any of the cards does not exist
+    pub fn from_names(names: &[String]) -> Option<Self> {
+        let cards = names.iter().map(|a| create_card(a)).collect::<Option<Vec<_>>>()?;
+        Some(CardSet { cards })
+    }
+}
@@ -1,30 +1,101 @@
+use crate::cost::Cost;
+use crate::dice_set::ElementType;
 use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::hooks::HookEvent;
 use crate::operation_context::OperationContext;
+use crate::reactions::DamageType;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum CardKind {
+    Event,
+    Food,
+    // Character name, skill used when the card is played
+    Talent(&'static str, SkillType),
+    // Element that two characters in the deck need to have
+    Resonance(ElementType),
+}
 
 pub trait ActionCard: Send + Sync {
-    fn use_card(&self, info: OperationContext, env: &mut GameEnvironment) {}
+    fn name(&self) -> &'static str;
+    fn kind(&self) -> CardKind {
+        CardKind::Event
+    }
+    fn cost(&self) -> Cost {
+        Cost::default()
+    }
+    // Card specific conditions for playing the card
+    fn can_use(&self, _info: &OperationContext, _env: &GameEnvironment) -> bool {
+        true
+    }
+    // Whether playing the card ends the turn
+    fn is_combat_action(&self) -> bool {
+        false
+    }
+    fn use_card(&self, _info: OperationContext, _env: &mut GameEnvironment) {}
 }
 
 pub trait SupportCard: Send + Sync {
-    fn on_created(&self, subject_player: usize, env: &mut GameEnvironment) {}
-    fn on_turn_start(&self, subject_player: usize, env: &mut GameEnvironment) {}
-    fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {}
+    fn name(&self) -> &'static str;
+    // The support is discarded at round start once no usages are left
+    fn remaining_uses(&self) -> usize;
+    fn on_created(&self, _subject_player: usize, _env: &mut GameEnvironment) {}
+    fn on_turn_start(&self, _subject_player: usize, _env: &mut GameEnvironment) {}
+    fn on_turn_end(&self, _subject_player: usize, _env: &mut GameEnvironment) {}
+    // Receives every hook event, round start and end are forwarded by default
+    fn on_event(&self, subject_player: usize, event: &HookEvent, env: &mut GameEnvironment) {
+        match event {
+            HookEvent::RoundStart(_) => self.on_turn_start(subject_player, env),
+            HookEvent::RoundEnd(_) => self.on_turn_end(subject_player, env),
+            _ => {}
+        }
+    }
 }
 
 pub trait SummonedCard: Send + Sync {
-    fn on_attacked(&self, subject_player: usize, env: &mut GameEnvironment) {}
-    fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {}
+    fn name(&self) -> &'static str;
+    fn on_attacked(&self, _subject_player: usize, _env: &mut GameEnvironment) {}
+    fn on_turn_end(&self, _subject_player: usize, _env: &mut GameEnvironment) {}
+    // Receives every hook event, non-piercing damage to the team and round end are forwarded by default
+    fn on_event(&self, subject_player: usize, event: &HookEvent, env: &mut GameEnvironment) {
+        match event {
+            HookEvent::Damage(result)
+                if result.target.player == subject_player && result.damage_type != DamageType::Piercing => {
+                self.on_attacked(subject_player, env);
+            }
+            HookEvent::RoundEnd(_) => self.on_turn_end(subject_player, env),
+            _ => {}
+        }
+    }
     fn remaining_uses(&self) -> usize;
+    // Called when the same summon is summoned again while still on the field
+    fn refresh(&self) {}
 }
 
 #[derive(Copy, Clone)]
 pub struct EmptyCard {}
 
-impl ActionCard for EmptyCard {}
+impl ActionCard for EmptyCard {
+    fn name(&self) -> &'static str {
+        "Empty"
+    }
+}
 
-impl SupportCard for EmptyCard {}
+impl SupportCard for EmptyCard {
+    fn name(&self) -> &'static str {
+        "Empty"
+    }
+
+    fn remaining_uses(&self) -> usize {
+        0
+    }
+}
 
 impl SummonedCard for EmptyCard {
+    fn name(&self) -> &'static str {
+        "Empty"
+    }
+
     fn remaining_uses(&self) -> usize {
         1
     }
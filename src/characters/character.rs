@@ -1,7 +1,12 @@
 use std::sync::Arc;
 use crate::operation_context::OperationContext;
+use crate::characters::definition::CharacterDefinition;
+use crate::cost::Cost;
 use crate::dice_set::ElementType;
 use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::reactions::Frozen;
+use crate::status::{add_status, find_status, Status};
 
 pub trait CharacterHandler: Send + Sync {
     fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment);
@@ -10,11 +15,93 @@ pub trait CharacterHandler: Send + Sync {
 }
 
 pub struct Character {
-    pub name: &'static str,
+    pub name: String,
+    pub weapon: String,
+    pub faction: String,
+    // Names of the normal attack, elemental skill and burst
+    pub skill_names: [String; 3],
     pub max_hp: usize,
     pub hp: usize,
-    pub e_cost: usize,
-    pub q_cost: usize,
+    pub normal_cost: Cost,
+    pub e_cost: Cost,
+    pub q_cost: Cost,
+    // Base damage of each skill, in the order of SkillType
+    pub skill_damage: [usize; 3],
+    pub energy: usize,
+    pub max_energy: usize,
     pub element: ElementType,
+    pub applied_elements: Vec<ElementType>,
+    pub statuses: Vec<Arc<dyn Status>>,
+    pub defeated: bool,
     pub handler: Arc<dyn CharacterHandler>
 }
+
+impl Character {
+    pub fn new(definition: &CharacterDefinition, handler: Arc<dyn CharacterHandler>) -> Self {
+        Character {
+            name: definition.name.clone(),
+            weapon: definition.weapon.clone(),
+            faction: definition.faction.clone(),
+            skill_names: [
+                definition.normal_attack.name.clone(),
+                definition.e_skill.name.clone(),
+                definition.q_skill.name.clone(),
+            ],
+            max_hp: definition.hp,
+            hp: definition.hp,
+            normal_cost: definition.normal_attack.cost.clone(),
+            e_cost: definition.e_skill.cost.clone(),
+            q_cost: definition.q_skill.cost.clone(),
+            skill_damage: [
+                definition.normal_attack.base_damage(),
+                definition.e_skill.base_damage(),
+                definition.q_skill.base_damage(),
+            ],
+            energy: 0,
+            max_energy: definition.max_energy,
+            element: definition.element,
+            applied_elements: vec![],
+            statuses: vec![],
+            defeated: false,
+            handler,
+        }
+    }
+
+    pub fn skill_cost(&self, skill: SkillType) -> &Cost {
+        match skill {
+            SkillType::NormalAttack => &self.normal_cost,
+            SkillType::ESkill => &self.e_cost,
+            SkillType::QSkill => &self.q_cost,
+        }
+    }
+
+    pub fn skill_damage(&self, skill: SkillType) -> usize {
+        match skill {
+            SkillType::NormalAttack => self.skill_damage[0],
+            SkillType::ESkill => self.skill_damage[1],
+            SkillType::QSkill => self.skill_damage[2],
+        }
+    }
+
+    pub fn heal(&mut self, amount: usize) {
+        if !self.defeated {
+            self.hp = (self.hp + amount).min(self.max_hp);
+        }
+    }
+
+    pub fn gain_energy(&mut self, amount: usize) {
+        self.energy = (self.energy + amount).min(self.max_energy);
+    }
+
+    pub fn add_status(&mut self, status: Arc<dyn Status>) {
+        add_status(&mut self.statuses, status);
+    }
+
+    pub fn find_status(&self, name: &str) -> Option<&Arc<dyn Status>> {
+        find_status(&self.statuses, name)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.find_status(Frozen::NAME).is_some()
+    }
+}
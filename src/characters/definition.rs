@@ -0,0 +1,92 @@
+use serde::Deserialize;
+use crate::cost::Cost;
+use crate::damage::DamageKind;
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::operation_context::OperationContext;
+use crate::reactions::DamageType;
+use crate::characters::character::CharacterHandler;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SkillDefinition {
+    pub name: String,
+    pub cost: Cost,
+    #[serde(default)]
+    pub damage: usize,
+    // Physical damage if not set
+    #[serde(default)]
+    pub element: Option<ElementType>,
+    // Piercing damage dealt to the opponent's standby characters
+    #[serde(default)]
+    pub piercing: usize,
+}
+
+impl SkillDefinition {
+    // Damage dealt to the opponent in total, ignoring reactions and statuses
+    pub fn base_damage(&self) -> usize {
+        self.damage + self.piercing
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self.element {
+            Some(element) => DamageType::Elemental(element),
+            None => DamageType::Physical,
+        }
+    }
+
+    // Deals the damage described by the definition
+    pub fn apply(&self, info: &OperationContext, env: &mut GameEnvironment, kind: DamageKind) {
+        if self.piercing > 0 {
+            for other in env.other_characters(info.target()) {
+                env.deal_damage(info.source(), other, self.piercing, DamageType::Piercing, kind);
+            }
+        }
+
+        if self.damage > 0 {
+            env.deal_damage(info.source(), info.target(), self.damage, self.damage_type(), kind);
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CharacterDefinition {
+    pub name: String,
+    pub hp: usize,
+    pub element: ElementType,
+    pub weapon: String,
+    pub faction: String,
+    pub max_energy: usize,
+    // Name of the Rust handler for characters with unusual effects
+    #[serde(default)]
+    pub handler: Option<String>,
+    pub normal_attack: SkillDefinition,
+    pub e_skill: SkillDefinition,
+    pub q_skill: SkillDefinition,
+}
+
+// Handler for characters whose skills only deal damage
+pub struct DataDrivenHandler {
+    definition: CharacterDefinition,
+}
+
+impl DataDrivenHandler {
+    pub fn new(definition: &CharacterDefinition) -> Self {
+        DataDrivenHandler {
+            definition: definition.clone(),
+        }
+    }
+}
+
+impl CharacterHandler for DataDrivenHandler {
+    fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.normal_attack.apply(&info, env, DamageKind::NormalAttack);
+    }
+
+    fn on_e_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.e_skill.apply(&info, env, DamageKind::ElementalSkill);
+    }
+
+    fn on_q_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.q_skill.apply(&info, env, DamageKind::ElementalBurst);
+    }
+}
@@ -1,67 +1,69 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use crate::operation_context::OperationContext;
 use crate::cards::SummonedCard;
-use crate::characters::character::{Character, CharacterHandler};
+use crate::characters::character::CharacterHandler;
+use crate::characters::definition::CharacterDefinition;
 use crate::dice_set::ElementType;
 use crate::game_environment::GameEnvironment;
+use crate::damage::{CharacterRef, DamageKind, DamageSource};
+use crate::reactions::DamageType;
 
 struct FischlHandler {
+    definition: CharacterDefinition,
     oz: Arc<Oz>,
 }
 
 struct Oz {
-    lifetime: usize,
+    lifetime: AtomicUsize,
 }
 
 impl SummonedCard for Oz {
+    fn name(&self) -> &'static str {
+        "Oz"
+    }
+
     fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {
-        let opponent = &mut env.players[1 - subject_player];
-        opponent.characters[opponent.active_character].hp -= 1;
+        let target_player = 1 - subject_player;
+        env.deal_damage(
+            DamageSource { player: subject_player, character: None },
+            CharacterRef { player: target_player, character: env.players[target_player].active_character },
+            1,
+            DamageType::Elemental(ElementType::Electro),
+            DamageKind::Summon,
+        );
+        self.lifetime.fetch_sub(1, Ordering::Relaxed);
     }
 
     fn remaining_uses(&self) -> usize {
-        self.lifetime
+        self.lifetime.load(Ordering::Relaxed)
     }
-}
 
-impl Default for FischlHandler {
-    fn default() -> Self {
-        FischlHandler {
-            oz: Arc::new(Oz { lifetime: 0 }),
-        }
+    fn refresh(&self) {
+        self.lifetime.store(2, Ordering::Relaxed);
     }
 }
 
 impl CharacterHandler for FischlHandler {
     fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        env.players[info.target_player].characters[info.target_character].hp -= 2;
+        self.definition.normal_attack.apply(&info, env, DamageKind::NormalAttack);
     }
 
     fn on_e_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        if self.oz.lifetime == 0 {
-            env.players[info.subject_player].insert_summoned(self.oz.clone());
-        }
+        self.definition.e_skill.apply(&info, env, DamageKind::ElementalSkill);
 
-        // This is safe because oz will only be read in one thread
-        unsafe {
-            let oz_raw = Arc::into_raw(self.oz.clone()) as *mut Oz;
-            (*oz_raw).lifetime = 2;
-        }
+        self.oz.refresh();
+        env.summon(info.subject_player, self.oz.clone());
     }
 
     fn on_q_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        todo!()
+        self.definition.q_skill.apply(&info, env, DamageKind::ElementalBurst);
     }
 }
 
-pub fn fischl() -> Character {
-    Character {
-        name: "Fischl",
-        max_hp: 10,
-        hp: 10,
-        e_cost: 3,
-        q_cost: 4,
-        element: ElementType::Electro,
-        handler: Arc::new(FischlHandler::default()),
-    }
-}
\ No newline at end of file
+pub fn fischl(definition: &CharacterDefinition) -> Arc<dyn CharacterHandler> {
+    Arc::new(FischlHandler {
+        definition: definition.clone(),
+        oz: Arc::new(Oz { lifetime: AtomicUsize::new(0) }),
+    })
+}
@@ -1,39 +1,66 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::cards::SummonedCard;
 use crate::operation_context::OperationContext;
-use crate::characters::character::{Character, CharacterHandler};
+use crate::characters::character::CharacterHandler;
+use crate::characters::definition::CharacterDefinition;
 use crate::dice_set::ElementType;
 use crate::game_environment::GameEnvironment;
+use crate::damage::{CharacterRef, DamageKind, DamageSource};
+use crate::reactions::DamageType;
 
-struct GanyuHandler {}
+struct GanyuHandler {
+    definition: CharacterDefinition,
+}
+
+struct SacredCryoPearl {
+    usages: AtomicUsize,
+}
+
+impl SummonedCard for SacredCryoPearl {
+    fn name(&self) -> &'static str {
+        "Sacred Cryo Pearl"
+    }
+
+    fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {
+        let source = DamageSource { player: subject_player, character: None };
+        let target_player = 1 - subject_player;
+        let target = CharacterRef { player: target_player, character: env.players[target_player].active_character };
+
+        for other in env.other_characters(target) {
+            env.deal_damage(source, other, 1, DamageType::Piercing, DamageKind::Summon);
+        }
 
-impl Default for GanyuHandler {
-    fn default() -> Self {
-        GanyuHandler {}
+        env.deal_damage(source, target, 1, DamageType::Elemental(ElementType::Cryo), DamageKind::Summon);
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+
+    fn refresh(&self) {
+        self.usages.store(2, Ordering::Relaxed);
     }
 }
 
 impl CharacterHandler for GanyuHandler {
     fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        todo!()
+        self.definition.normal_attack.apply(&info, env, DamageKind::NormalAttack);
     }
 
     fn on_e_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        todo!()
+        self.definition.e_skill.apply(&info, env, DamageKind::ElementalSkill);
     }
 
     fn on_q_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        todo!()
+        self.definition.q_skill.apply(&info, env, DamageKind::ElementalBurst);
+        env.summon(info.subject_player, Arc::new(SacredCryoPearl { usages: AtomicUsize::new(2) }));
     }
 }
 
-pub fn ganyu() -> Character {
-    Character {
-        name: "Ganyu",
-        max_hp: 10,
-        hp: 10,
-        e_cost: 1,
-        q_cost: 3,
-        element: ElementType::Cryo,
-        handler: Arc::new(GanyuHandler::default()),
-    }
-}
\ No newline at end of file
+pub fn ganyu(definition: &CharacterDefinition) -> Arc<dyn CharacterHandler> {
+    Arc::new(GanyuHandler {
+        definition: definition.clone(),
+    })
+}
@@ -1,4 +1,7 @@
 pub mod yoimiya;
 pub mod fischl;
 pub mod ganyu;
+pub mod mona;
 pub mod character;
+pub mod definition;
+pub mod registry;
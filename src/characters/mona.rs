@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::operation_context::OperationContext;
+use crate::cards::SummonedCard;
+use crate::characters::character::CharacterHandler;
+use crate::characters::definition::CharacterDefinition;
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::damage::{CharacterRef, DamageEvent, DamageKind, DamageSource, ModifierStage};
+use crate::reactions::DamageType;
+use crate::status::{Status, StatusCounter, StatusOwner};
+
+struct MonaHandler {
+    definition: CharacterDefinition,
+}
+
+struct Reflection {
+    usages: AtomicUsize,
+}
+
+impl SummonedCard for Reflection {
+    fn name(&self) -> &'static str {
+        "Reflection"
+    }
+
+    fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {
+        let target_player = 1 - subject_player;
+        env.deal_damage(
+            DamageSource { player: subject_player, character: None },
+            CharacterRef { player: target_player, character: env.players[target_player].active_character },
+            1,
+            DamageType::Elemental(ElementType::Hydro),
+            DamageKind::Summon,
+        );
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+
+    fn refresh(&self) {
+        self.usages.store(1, Ordering::Relaxed);
+    }
+}
+
+// Doubles the next skill damage dealt by the team
+struct IllusoryBubble {
+    counter: StatusCounter,
+}
+
+impl Status for IllusoryBubble {
+    fn name(&self) -> &'static str {
+        "Illusory Bubble"
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn stage(&self) -> ModifierStage {
+        ModifierStage::Multiplicative
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        let skill_damage = matches!(event.kind, DamageKind::NormalAttack | DamageKind::ElementalSkill | DamageKind::ElementalBurst);
+        if !self.expired() && event.source.player == owner.player && skill_damage {
+            event.amount *= 2;
+            self.counter.consume();
+        }
+    }
+
+    fn refresh(&self) {
+        self.counter.set_usages(1);
+    }
+}
+
+impl CharacterHandler for MonaHandler {
+    fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.normal_attack.apply(&info, env, DamageKind::NormalAttack);
+    }
+
+    fn on_e_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.e_skill.apply(&info, env, DamageKind::ElementalSkill);
+        env.summon(info.subject_player, Arc::new(Reflection { usages: AtomicUsize::new(1) }));
+    }
+
+    fn on_q_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
+        self.definition.q_skill.apply(&info, env, DamageKind::ElementalBurst);
+        env.players[info.subject_player].add_combat_status(Arc::new(IllusoryBubble {
+            counter: StatusCounter::new(1, StatusCounter::UNLIMITED),
+        }));
+    }
+}
+
+pub fn mona(definition: &CharacterDefinition) -> Arc<dyn CharacterHandler> {
+    Arc::new(MonaHandler {
+        definition: definition.clone(),
+    })
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use crate::characters::character::{Character, CharacterHandler};
+use crate::characters::definition::{CharacterDefinition, DataDrivenHandler};
+use crate::characters::fischl::fischl;
+use crate::characters::ganyu::ganyu;
+use crate::characters::mona::mona;
+use crate::characters::yoimiya::yoimiya;
+
+pub const CHARACTER_DATA_DIR: &str = "data/characters";
+
+type HandlerFactory = fn(&CharacterDefinition) -> Arc<dyn CharacterHandler>;
+
+// Rust handlers for characters with effects that cannot be described in data files
+fn custom_handlers() -> HashMap<&'static str, HandlerFactory> {
+    let mut handlers: HashMap<&'static str, HandlerFactory> = HashMap::new();
+    handlers.insert("Yoimiya", yoimiya);
+    handlers.insert("Fischl", fischl);
+    handlers.insert("Ganyu", ganyu);
+    handlers.insert("Mona", mona);
+    handlers
+}
+
+pub struct CharacterRegistry {
+    definitions: HashMap<String, CharacterDefinition>,
+}
+
+impl CharacterRegistry {
+    // Loads every json file in the directory as a character definition
+    pub fn load(dir: &str) -> Result<Self, String> {
+        let handlers = custom_handlers();
+        let mut definitions = HashMap::new();
+
+        let entries = fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {}", dir, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|a| a.to_str()) != Some("json") {
+                continue;
+            }
+
+            let text = fs::read_to_string(&path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            let definition: CharacterDefinition = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid character file {}: {}", path.display(), e))?;
+
+            if let Some(handler) = definition.handler.as_ref() {
+                if !handlers.contains_key(handler.as_str()) {
+                    return Err(format!("Unknown handler {} in {}", handler, path.display()));
+                }
+            }
+
+            if definitions.contains_key(&definition.name) {
+                return Err(format!("Duplicate character {} in {}", definition.name, path.display()));
+            }
+
+            definitions.insert(definition.name.clone(), definition);
+        }
+
+        println!("Loaded {} characters from {}", definitions.len(), dir);
+        Ok(CharacterRegistry { definitions })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CharacterDefinition> {
+        self.definitions.get(name)
+    }
+
+    pub fn create(&self, name: &str) -> Option<Character> {
+        let definition = self.definitions.get(name)?;
+        let handler = match definition.handler.as_ref() {
+            Some(handler) => custom_handlers()[handler.as_str()](definition),
+            None => Arc::new(DataDrivenHandler::new(definition)),
+        };
+
+        Some(Character::new(definition, handler))
+    }
+}
@@ -1,44 +1,73 @@
 use std::sync::Arc;
 use crate::operation_context::OperationContext;
-use crate::characters::character::{Character, CharacterHandler};
+use crate::characters::character::CharacterHandler;
+use crate::characters::definition::CharacterDefinition;
 use crate::dice_set::ElementType;
 use crate::game_environment::GameEnvironment;
+use crate::damage::{DamageEvent, DamageKind};
+use crate::reactions::DamageType;
+use crate::status::{Status, StatusCounter, StatusOwner};
 
-struct YoimiyaHandler {
-    pyro_attached: bool,
+// Normal attacks deal Pyro damage with +1 damage
+struct NiwabiEnshou {
+    counter: StatusCounter,
+}
+
+impl NiwabiEnshou {
+    const NAME: &'static str = "Niwabi Enshou";
+    const USAGES: usize = 3;
 }
 
-impl Default for YoimiyaHandler {
-    fn default() -> Self {
-        YoimiyaHandler {
-            pyro_attached: false
+impl Status for NiwabiEnshou {
+    fn name(&self) -> &'static str {
+        NiwabiEnshou::NAME
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        if event.source.player == owner.player && event.source.character == owner.character
+            && event.kind == DamageKind::NormalAttack {
+            event.amount += 1;
+            self.counter.consume();
         }
     }
+
+    fn refresh(&self) {
+        self.counter.set_usages(NiwabiEnshou::USAGES);
+    }
+}
+
+struct YoimiyaHandler {
+    definition: CharacterDefinition,
 }
 
 impl CharacterHandler for YoimiyaHandler {
     fn on_normal_attack(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        let dmg = if self.pyro_attached { 4 } else { 2 };
-        env.players[info.target_player].characters[info.target_character].hp -= dmg;
+        let skill = &self.definition.normal_attack;
+        let character = &env.players[info.subject_player].characters[info.subject_character];
+        if character.find_status(NiwabiEnshou::NAME).is_some() {
+            env.deal_damage(info.source(), info.target(), skill.damage, DamageType::Elemental(ElementType::Pyro), DamageKind::NormalAttack);
+        } else {
+            skill.apply(&info, env, DamageKind::NormalAttack);
+        }
     }
 
     fn on_e_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        self.pyro_attached = true;
+        env.players[info.subject_player].characters[info.subject_character].add_status(Arc::new(NiwabiEnshou {
+            counter: StatusCounter::new(NiwabiEnshou::USAGES, StatusCounter::UNLIMITED),
+        }));
     }
 
     fn on_q_skill(&mut self, info: OperationContext, env: &mut GameEnvironment) {
-        todo!()
+        self.definition.q_skill.apply(&info, env, DamageKind::ElementalBurst);
     }
 }
 
-pub fn yoimiya() -> Character {
-    Character {
-        name: "Yoimiya",
-        max_hp: 10,
-        hp: 10,
-        e_cost: 1,
-        q_cost: 3,
-        element: ElementType::Pyro,
-        handler: Arc::new(YoimiyaHandler::default()),
-    }
-}
\ No newline at end of file
+pub fn yoimiya(definition: &CharacterDefinition) -> Arc<dyn CharacterHandler> {
+    Arc::new(YoimiyaHandler {
+        definition: definition.clone(),
+    })
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use crate::bot::BotKind;
+use crate::deck::Deck;
+use crate::game_events::SkillType;
+use crate::lobby::RoomOptions;
+
+// Messages sent by the client
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    // Has to be the first message of a session
+    Hello {
+        version: u32,
+    },
+    JoinRoom {
+        room: usize,
+        // Players joining without a deck use the default one
+        #[serde(default)]
+        deck: Option<Deck>,
+        // Required by private rooms
+        #[serde(default)]
+        password: Option<String>,
+    },
+    // Creates a room with the next free id and joins it
+    CreateRoom {
+        #[serde(flatten)]
+        options: RoomOptions,
+        #[serde(default)]
+        deck: Option<Deck>,
+        // Makes the game reproducible, only accepted with the practice option
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    ListRooms,
+    // Waits until another player queues and joins a new room with them
+    QuickMatch {
+        #[serde(default)]
+        deck: Option<Deck>,
+    },
+    LeaveQueue,
+    // Fills the other player slot of the practice room the player is waiting in
+    AddBot {
+        kind: BotKind,
+    },
+    // Takes over a player of a room after losing the connection
+    Resume {
+        token: String,
+    },
+    // Joins a room read-only, full reveals both players' hands and dice
+    Spectate {
+        room: usize,
+        #[serde(default)]
+        full: bool,
+        // Seconds state updates are held back, for streaming
+        #[serde(default)]
+        delay: u64,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    SwapCards {
+        cards: Vec<usize>,
+    },
+    #[serde(alias = "RerollDice")]
+    Reroll {
+        dices: Vec<usize>,
+    },
+    // Dices are selected by the server when left empty
+    UseSkill {
+        skill: SkillType,
+        #[serde(default)]
+        dices: Vec<usize>,
+    },
+    #[serde(alias = "UseActionCard")]
+    PlayCard {
+        card: usize,
+        target: usize,
+        #[serde(default)]
+        dices: Vec<usize>,
+    },
+    #[serde(alias = "ChangeActive")]
+    Switch {
+        character: usize,
+        #[serde(default)]
+        dices: Vec<usize>,
+    },
+    ChooseActive {
+        character: usize,
+    },
+    // Discards a card to convert a die to the active character's element
+    Tune {
+        card: usize,
+        dice: usize,
+    },
+    #[serde(alias = "DeclareRoundEnd")]
+    DeclareEnd,
+    Concede,
+    SetStateMode {
+        diff: bool,
+    },
+    AckState {
+        sequence: u64,
+    },
+    Resync,
+    // Replays are streamed as state updates, speed 2 plays twice as fast
+    WatchReplay {
+        replay: String,
+        #[serde(default)]
+        speed: Option<f64>,
+    },
+    SetReplaySpeed {
+        speed: f64,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    // Not valid JSON or missing fields
+    Malformed,
+    UnknownMessage,
+    UnsupportedVersion,
+    HandshakeRequired,
+    NotInRoom,
+    // The session already plays or spectates a room
+    AlreadyInRoom,
+    SpectatorsCannotAct,
+    ReplayUnavailable,
+}
@@ -0,0 +1,228 @@
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use crate::game_environment::GameEnvironment;
+use crate::game_events::GameEvent;
+use crate::phase::Phase;
+use crate::server_messages::ClockTickMessage;
+
+// Players forfeit when their clock runs out this many times in a row
+const MAX_TIMEOUTS: usize = 3;
+
+// Time limits of a room, no limit when None
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClockSettings {
+    // Time for every single action
+    pub action: Option<Duration>,
+    // Time for all actions of a round together
+    pub round: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct Clocks {
+    pub settings: ClockSettings,
+    // When each player started the action the game is waiting for
+    action_since: [Option<Instant>; 2],
+    round_used: [Duration; 2],
+    // Timeouts since the player last acted in time
+    timeouts: [usize; 2],
+    // Set while the action performed for a player who timed out is pending
+    timed_out: [bool; 2],
+    // The round clock only counts as a timeout once per round
+    round_expired: [bool; 2],
+    last_update: Instant,
+}
+
+impl Clocks {
+    pub fn new(settings: ClockSettings) -> Self {
+        Clocks {
+            settings,
+            action_since: [None, None],
+            round_used: [Duration::ZERO, Duration::ZERO],
+            timeouts: [0, 0],
+            timed_out: [false, false],
+            round_expired: [false, false],
+            last_update: Instant::now(),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.settings.action.is_some() || self.settings.round.is_some()
+    }
+
+    // Called after the player acted, the next action gets a full clock
+    pub fn reset_action(&mut self, index: usize) {
+        self.action_since[index] = None;
+        if !std::mem::take(&mut self.timed_out[index]) {
+            self.timeouts[index] = 0;
+        }
+    }
+
+    pub fn reset_round(&mut self) {
+        self.round_used = [Duration::ZERO, Duration::ZERO];
+        self.round_expired = [false, false];
+    }
+
+    fn action_remaining(&self, index: usize) -> Option<Duration> {
+        let since = self.action_since[index]?;
+        Some(self.settings.action?.saturating_sub(since.elapsed()))
+    }
+
+    fn round_remaining(&self, index: usize) -> Option<Duration> {
+        Some(self.settings.round?.saturating_sub(self.round_used[index]))
+    }
+}
+
+impl Default for Clocks {
+    fn default() -> Self {
+        Clocks::new(ClockSettings::default())
+    }
+}
+
+impl GameEnvironment {
+    // Event performed for the player when their clock runs out, None if the game is not waiting for them
+    fn timeout_action(&self, index: usize) -> Option<GameEvent> {
+        let player = &self.players[index];
+        match self.phase {
+            Phase::Mulligan if !self.mulligan_done[index] => Some(GameEvent::SwapCards(index, vec![])),
+            // Keeps the rolled dice
+            Phase::Reroll if player.reroll_chances > 0 => Some(GameEvent::RerollDice(index, vec![])),
+            _ if player.choosing_active => Some(GameEvent::ChooseActive(index, *player.alive_characters().first()?)),
+            Phase::Action if self.current_turn == index && !self.declared_end[index]
+                && !self.players.iter().any(|a| a.choosing_active) => Some(GameEvent::DeclareRoundEnd(index)),
+            _ => None,
+        }
+    }
+
+    // Runs the clocks of the players the game is waiting for, called on every tick
+    pub fn update_clocks(&mut self, send: &Sender<GameEvent>) {
+        let elapsed = self.clocks.last_update.elapsed();
+        self.clocks.last_update = Instant::now();
+        if !self.clocks.enabled() || self.result.is_some() {
+            return;
+        }
+
+        for index in 0..2usize {
+            let Some(action) = self.timeout_action(index) else {
+                self.clocks.action_since[index] = None;
+                continue;
+            };
+
+            if self.clocks.action_since[index].is_none() {
+                self.clocks.action_since[index] = Some(Instant::now());
+            } else {
+                self.clocks.round_used[index] += elapsed;
+            }
+
+            let action_remaining = self.clocks.action_remaining(index);
+            let round_remaining = self.clocks.round_remaining(index);
+            for addr in self.session_addr.iter().flatten() {
+                addr.do_send(ClockTickMessage {
+                    player_index: index,
+                    action_remaining: action_remaining.map(|a| a.as_secs()),
+                    round_remaining: round_remaining.map(|a| a.as_secs()),
+                    timeouts: self.clocks.timeouts[index],
+                });
+            }
+
+            let action_expired = action_remaining.is_some_and(|a| a.is_zero());
+            let round_expired = round_remaining.is_some_and(|a| a.is_zero());
+            if action_expired || round_expired {
+                // Once the round clock is used up every action of the round is performed right away
+                if action_expired || !self.clocks.round_expired[index] {
+                    self.clocks.timeouts[index] += 1;
+                }
+                self.clocks.round_expired[index] |= round_expired;
+                self.clocks.timed_out[index] = true;
+                self.clocks.action_since[index] = None;
+
+                let action = if self.clocks.timeouts[index] >= MAX_TIMEOUTS {
+                    println!("Player {} timed out too often", index);
+                    GameEvent::Concede(index)
+                } else {
+                    println!("Player {} timed out, performing {:?}", index, action);
+                    action
+                };
+                send.send(action).expect("Game loop has stopped");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use super::*;
+    use crate::game_environment::GameResult;
+    use crate::game_environment::tests::{action_phase, omni_dice, run};
+    use crate::game_events::SkillType;
+
+    fn clocks(action: Option<Duration>, round: Option<Duration>) -> Clocks {
+        Clocks::new(ClockSettings { action, round })
+    }
+
+    // Runs the clocks once and performs the automatic actions
+    fn tick(env: &mut GameEnvironment) -> Vec<GameEvent> {
+        let (send, recv) = channel();
+        env.update_clocks(&send);
+        let events: Vec<GameEvent> = recv.try_iter().collect();
+        for event in events.iter() {
+            run(env, event.clone());
+        }
+        events
+    }
+
+    #[test]
+    fn round_end_is_declared_on_timeout() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        env.clocks = clocks(Some(Duration::ZERO), None);
+
+        assert!(matches!(tick(&mut env)[..], [GameEvent::DeclareRoundEnd(a)] if a == id));
+        assert!(env.declared_end[id]);
+        assert_eq!(env.clocks.timeouts[id], 1);
+        assert_eq!(env.current_turn, 1 - id);
+    }
+
+    #[test]
+    fn acting_in_time_resets_the_timeouts() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        env.clocks = clocks(Some(Duration::from_secs(60)), None);
+        env.clocks.timeouts[id] = MAX_TIMEOUTS - 1;
+
+        omni_dice(&mut env, id);
+        run(&mut env, GameEvent::UseSkill(id, SkillType::NormalAttack, vec![]));
+        assert_eq!(env.clocks.timeouts[id], 0);
+    }
+
+    #[test]
+    fn too_many_timeouts_in_a_row_forfeit() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        env.clocks = clocks(Some(Duration::ZERO), None);
+        env.clocks.timeouts[id] = MAX_TIMEOUTS - 1;
+
+        assert!(matches!(tick(&mut env)[..], [GameEvent::Concede(a)] if a == id));
+        assert_eq!(env.result, Some(GameResult::Winner(1 - id)));
+    }
+
+    #[test]
+    fn used_up_round_clock_counts_once() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        env.clocks = clocks(None, Some(Duration::ZERO));
+
+        // Both players are out of time and declare the round end one after another
+        assert_eq!(tick(&mut env).len(), 1);
+        assert_eq!(tick(&mut env).len(), 1);
+        assert_eq!(env.round, 2);
+        assert_eq!(env.clocks.timeouts, [1, 1]);
+
+        // A new round counts again, but only once for all actions of the round
+        assert!(matches!(tick(&mut env)[..], [GameEvent::RerollDice(..), GameEvent::RerollDice(..)]));
+        assert_eq!(env.clocks.timeouts, [2, 2]);
+        assert!(matches!(tick(&mut env)[..], [GameEvent::DeclareRoundEnd(a)] if a == id));
+        assert_eq!(env.clocks.timeouts, [2, 2]);
+        assert!(env.result.is_none());
+    }
+}
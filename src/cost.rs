@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::dice_set::ElementType;
+use crate::game_events::SkillType;
+use crate::status::Status;
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cost {
+    // Dice of a specific element
+    pub elemental: Option<(ElementType, usize)>,
+    // Dice that all have to be of the same element
+    pub same: usize,
+    // Dice of any element
+    pub unaligned: usize,
+    pub energy: usize,
+}
+
+impl Cost {
+    pub fn elemental(ty: ElementType, num: usize) -> Self {
+        Cost {
+            elemental: Some((ty, num)),
+            ..Default::default()
+        }
+    }
+
+    pub fn same(num: usize) -> Self {
+        Cost {
+            same: num,
+            ..Default::default()
+        }
+    }
+
+    pub fn unaligned(num: usize) -> Self {
+        Cost {
+            unaligned: num,
+            ..Default::default()
+        }
+    }
+
+    pub fn dice_count(&self) -> usize {
+        self.elemental.map_or(0, |(_, num)| num) + self.same + self.unaligned
+    }
+
+    // Reduces the dice needed, elemental dice are reduced first
+    pub fn reduce_dice(&mut self, mut amount: usize) {
+        if let Some((ty, num)) = self.elemental {
+            let reduced = num.min(amount);
+            amount -= reduced;
+            self.elemental = if num == reduced { None } else { Some((ty, num - reduced)) };
+        }
+
+        let reduced = self.same.min(amount);
+        self.same -= reduced;
+        amount -= reduced;
+
+        self.unaligned -= self.unaligned.min(amount);
+    }
+}
+
+// What a cost is being paid for
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum CostSubject {
+    // Character index, skill
+    Skill(usize, SkillType),
+    ActionCard,
+    ChangeActive,
+}
+
+pub trait CostModifier: Send + Sync {
+    // Reduces the cost, returns whether the modifier applies
+    fn modify(&self, subject: CostSubject, cost: &mut Cost) -> bool;
+    // Called after a cost reduced by this modifier is paid
+    fn consume(&self) {}
+    fn remaining_uses(&self) -> usize;
+}
+
+// A reduction applied to a cost, a usage is consumed once the cost is paid
+#[derive(Clone)]
+pub enum AppliedModifier {
+    Modifier(Arc<dyn CostModifier>),
+    Status(Arc<dyn Status>),
+}
@@ -0,0 +1,58 @@
+use serde::Serialize;
+use crate::reactions::{DamageType, Reaction};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum DamageKind {
+    NormalAttack,
+    ElementalSkill,
+    ElementalBurst,
+    Summon,
+    Reaction,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub struct DamageSource {
+    pub player: usize,
+    // None when the damage comes from a summon or status
+    pub character: Option<usize>,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub struct CharacterRef {
+    pub player: usize,
+    pub character: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct DamageEvent {
+    pub source: DamageSource,
+    pub target: CharacterRef,
+    pub amount: usize,
+    pub damage_type: DamageType,
+    pub kind: DamageKind,
+    pub reaction: Option<Reaction>,
+    // Whether the target is the active character of its player
+    pub target_active: bool,
+    pub absorbed: usize,
+}
+
+// Statuses modify damage in this order
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub enum ModifierStage {
+    Additive,
+    Multiplicative,
+    Reduction,
+    Shield,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DamageResult {
+    pub source: DamageSource,
+    pub target: CharacterRef,
+    pub damage: usize,
+    pub damage_type: DamageType,
+    pub kind: DamageKind,
+    pub reaction: Option<Reaction>,
+    pub absorbed: usize,
+    pub remaining_hp: usize,
+}
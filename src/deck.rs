@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::action_cards::create_card;
+use crate::cards::CardKind;
+use crate::characters::registry::CharacterRegistry;
+use crate::lobby::Ruleset;
+
+pub const CHARACTER_COUNT: usize = 3;
+pub const CARD_COUNT: usize = 30;
+pub const MAX_COPIES: usize = 2;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Deck {
+    pub characters: Vec<String>,
+    pub cards: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum DeckError {
+    WrongCharacterCount(usize),
+    WrongCardCount(usize),
+    UnknownCharacter(String),
+    DuplicateCharacter(String),
+    UnknownCard(String),
+    TooManyCopies(String),
+    // Talent card whose character is not in the deck
+    TalentWithoutCharacter(String),
+    // Resonance card without two characters of its element
+    ResonanceWithoutElements(String),
+}
+
+impl Deck {
+    pub fn validate(&self, registry: &CharacterRegistry, ruleset: Ruleset) -> Result<(), DeckError> {
+        if self.characters.len() != CHARACTER_COUNT {
+            return Err(DeckError::WrongCharacterCount(self.characters.len()));
+        }
+
+        if ruleset == Ruleset::Standard && self.cards.len() != CARD_COUNT {
+            return Err(DeckError::WrongCardCount(self.cards.len()));
+        }
+
+        let mut elements = vec![];
+        for (i, name) in self.characters.iter().enumerate() {
+            let definition = registry.get(name).ok_or_else(|| DeckError::UnknownCharacter(name.clone()))?;
+            if self.characters[0..i].contains(name) {
+                return Err(DeckError::DuplicateCharacter(name.clone()));
+            }
+
+            elements.push(definition.element);
+        }
+
+        let mut copies = HashMap::<&String, usize>::new();
+        for name in self.cards.iter() {
+            let card = create_card(name).ok_or_else(|| DeckError::UnknownCard(name.clone()))?;
+
+            let count = copies.entry(name).or_insert(0);
+            *count += 1;
+            if ruleset == Ruleset::Standard && *count > MAX_COPIES {
+                return Err(DeckError::TooManyCopies(name.clone()));
+            }
+
+            match card.kind() {
+                CardKind::Talent(character, _) if !self.characters.iter().any(|a| a == character) => {
+                    return Err(DeckError::TalentWithoutCharacter(name.clone()));
+                }
+
+                CardKind::Resonance(element) if elements.iter().filter(|a| **a == element).count() < 2 => {
+                    return Err(DeckError::ResonanceWithoutElements(name.clone()));
+                }
+
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Deck used by players joining without submitting one
+impl Default for Deck {
+    fn default() -> Self {
+        let cards = [
+            "Starsigns",
+            "The Bestest Travel Companion!",
+            "Changing Shifts",
+            "Calx's Arts",
+            "Guardian's Oath",
+            "Sweet Madame",
+            "Mondstadt Hash Brown",
+            "Lotus Flower Crisp",
+            "Adeptus' Temptation",
+            "Jueyun Guoba",
+            "Northern Smoked Chicken",
+            "Teyvat Fried Egg",
+            "Naganohara Meteor Swarm",
+            "Stellar Predator",
+            "Undivided Heart",
+        ];
+
+        Deck {
+            characters: vec!["Yoimiya".to_string(), "Fischl".to_string(), "Ganyu".to_string()],
+            cards: cards.iter().flat_map(|a| [a.to_string(), a.to_string()]).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::characters::registry::CHARACTER_DATA_DIR;
+
+    fn registry() -> CharacterRegistry {
+        CharacterRegistry::load(CHARACTER_DATA_DIR).unwrap()
+    }
+
+    // Default deck with the first cards replaced
+    fn deck_with(characters: [&str; 3], cards: &[&str]) -> Deck {
+        let mut deck = Deck {
+            characters: characters.iter().map(|a| a.to_string()).collect(),
+            ..Deck::default()
+        };
+        for (i, card) in cards.iter().enumerate() {
+            deck.cards[i] = card.to_string();
+        }
+        deck
+    }
+
+    #[test]
+    fn default_deck_is_valid() {
+        assert_eq!(Deck::default().validate(&registry(), Ruleset::Standard), Ok(()));
+    }
+
+    #[test]
+    fn character_count_and_names() {
+        let registry = registry();
+        let mut deck = Deck::default();
+        deck.characters.pop();
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::WrongCharacterCount(2)));
+
+        let deck = deck_with(["Yoimiya", "Fischl", "Nobody"], &[]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::UnknownCharacter("Nobody".to_string())));
+
+        let deck = deck_with(["Yoimiya", "Fischl", "Yoimiya"], &[]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::DuplicateCharacter("Yoimiya".to_string())));
+    }
+
+    #[test]
+    fn card_count_and_copies() {
+        let registry = registry();
+        let mut deck = Deck::default();
+        deck.cards.pop();
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::WrongCardCount(29)));
+
+        // The default deck has two copies of every card
+        let deck = deck_with(["Yoimiya", "Fischl", "Ganyu"], &["Strategize", "Sweet Madame"]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::TooManyCopies("Sweet Madame".to_string())));
+
+        let deck = deck_with(["Yoimiya", "Fischl", "Ganyu"], &["Not a card"]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::UnknownCard("Not a card".to_string())));
+    }
+
+    #[test]
+    fn talent_needs_its_character() {
+        let deck = deck_with(["Yoimiya", "Fischl", "Razor"], &[]);
+        assert_eq!(deck.validate(&registry(), Ruleset::Standard), Err(DeckError::TalentWithoutCharacter("Undivided Heart".to_string())));
+    }
+
+    #[test]
+    fn resonance_needs_two_characters_of_its_element() {
+        let registry = registry();
+        let card = "Elemental Resonance: Woven Flames";
+
+        let deck = deck_with(["Yoimiya", "Fischl", "Ganyu"], &[card]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Err(DeckError::ResonanceWithoutElements(card.to_string())));
+
+        // Swaps out the Ganyu talent as well
+        let mut deck = deck_with(["Yoimiya", "Fischl", "Diluc"], &[card]);
+        deck.cards.retain(|a| a != "Undivided Heart");
+        deck.cards.extend(["Strategize".to_string(), "Strategize".to_string()]);
+        assert_eq!(deck.validate(&registry, Ruleset::Standard), Ok(()));
+    }
+
+    #[test]
+    fn casual_ruleset_ignores_size_and_copies() {
+        let registry = registry();
+        let deck = Deck {
+            characters: vec!["Yoimiya".to_string(), "Fischl".to_string(), "Ganyu".to_string()],
+            cards: vec!["Sweet Madame".to_string(); 5],
+        };
+        assert!(deck.validate(&registry, Ruleset::Standard).is_err());
+        assert_eq!(deck.validate(&registry, Ruleset::Casual), Ok(()));
+
+        // Unknown cards and broken card conditions are still rejected
+        let deck = deck_with(["Yoimiya", "Fischl", "Razor"], &[]);
+        assert_eq!(deck.validate(&registry, Ruleset::Casual), Err(DeckError::TalentWithoutCharacter("Undivided Heart".to_string())));
+    }
+}
@@ -2,10 +2,12 @@ use std::cmp::Ordering;
 use std::fmt;
 use rand::prelude::*;
 use int_enum::IntEnum;
+use serde::{Deserialize, Serialize};
+use crate::cost::Cost;
 use crate::dice_set::ElementType::Universal;
 
 #[repr(i8)]
-#[derive(PartialOrd, Ord, Clone, Copy, Eq, PartialEq, IntEnum, Debug)]
+#[derive(PartialOrd, Ord, Clone, Copy, Eq, PartialEq, IntEnum, Debug, Serialize, Deserialize)]
 pub enum ElementType {
     Universal = 0,
     Electro = 1,
@@ -39,28 +41,43 @@ impl Default for DiceSet {
 }
 
 impl DiceSet {
-    fn generate_dice(&mut self) -> ElementType {
-        let val = thread_rng().gen::<f64>() * 8.0;
-        let int_val = val.floor() as i8;
+    fn generate_dice(rng: &mut StdRng) -> ElementType {
+        let int_val = rng.gen_range(0..8);
         ElementType::from_int(int_val).unwrap()
     }
 
-    pub fn reroll_dice(&mut self, index: usize) {
-        self.dices[index] = self.generate_dice();
+    pub fn reroll_dice(&mut self, index: usize, rng: &mut StdRng) {
+        self.dices[index] = DiceSet::generate_dice(rng);
     }
 
-    pub fn roll_dices(&mut self) {
+    pub fn roll_dices(&mut self, rng: &mut StdRng) {
         self.dices.fill(ElementType::Null);
 
         for i in 0usize..8usize {
-            self.reroll_dice(i);
+            self.reroll_dice(i, rng);
         }
 
         self.dice_count = 8;
     }
 
+    pub fn add_dice(&mut self, ty: ElementType) {
+        if self.dice_count < self.dices.len() {
+            self.dices[self.dice_count] = ty;
+            self.dice_count += 1;
+        }
+    }
+
+    // Omni dice first, then dice of the given character elements in order, then the rest
     pub fn sort_dice(&mut self, character_types: Vec<ElementType>) {
+        let rank = |ty: &ElementType| {
+            character_types.iter().position(|a| a == ty).unwrap_or(character_types.len())
+        };
+
         self.dices.sort_by(|a, b| {
+            if a == b {
+                return Ordering::Equal;
+            }
+
             if *a == ElementType::Universal {
                 return Ordering::Less;
             }
@@ -69,28 +86,36 @@ impl DiceSet {
                 return Ordering::Greater;
             }
 
-            // Whether there are characters of element a
-            let is_a_used = character_types.contains(a);
-            if is_a_used == character_types.contains(b) {
-                a.int_value().cmp(&b.int_value())
-            } else {
-                if is_a_used {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            }
+            rank(a).cmp(&rank(b)).then(a.int_value().cmp(&b.int_value()))
         });
     }
 
-    pub fn find_dice(&self, must_same: bool, ty: ElementType, num: usize) -> Option<Vec<usize>> {
+    pub fn tune_dice(&mut self, index: usize, ty: ElementType) {
+        self.dices[index] = ty;
+    }
+
+    // Whether the die at index can pay for a die of the given element
+    pub fn matches(&self, index: usize, ty: ElementType) -> bool {
+        self.dices[index] == ty || self.dices[index] == Universal
+    }
+
+    // Finds num dice not in excluded, of type ty or all of the same type if ty is Null
+    pub fn find_dice(&self, must_same: bool, ty: ElementType, num: usize, excluded: &[usize]) -> Option<Vec<usize>> {
         let mut result = Vec::<usize>::new();
         let mut remaining_dices = num;
         let mut selected_type = ty;
 
+        if num == 0 {
+            return Some(result);
+        }
+
         if ty == ElementType::Null && must_same {
             let mut type_count = [0usize; 8];
             for i in 0usize..self.dice_count {
+                if excluded.contains(&i) {
+                    continue;
+                }
+
                 let element_index = self.dices[i].int_value() as usize;
                 type_count[element_index] += 1;
 
@@ -108,8 +133,12 @@ impl DiceSet {
         }
 
         for i in (0usize..self.dice_count).rev() {
+            if excluded.contains(&i) {
+                continue;
+            }
+
             let cond = if must_same {
-                self.dices[i] == selected_type || self.dices[i] == Universal
+                self.matches(i, selected_type)
             } else {
                 true
             };
@@ -130,6 +159,66 @@ impl DiceSet {
         }
     }
 
+    // Finds a selection of dice paying for the cost, energy is not checked
+    pub fn solve_cost(&self, cost: &Cost) -> Option<Vec<usize>> {
+        let mut result = Vec::<usize>::new();
+
+        if let Some((ty, num)) = cost.elemental {
+            result.extend(self.find_dice(true, ty, num, &result)?);
+        }
+
+        let same = self.find_dice(true, ElementType::Null, cost.same, &result)?;
+        result.extend(same);
+
+        let unaligned = self.find_dice(false, ElementType::Null, cost.unaligned, &result)?;
+        result.extend(unaligned);
+
+        Some(result)
+    }
+
+    // Whether the selected dice pay exactly for the cost, energy is not checked
+    pub fn check_cost(&self, indices: &[usize], cost: &Cost) -> bool {
+        if indices.len() != cost.dice_count() {
+            return false;
+        }
+
+        // Every die can only be spent once
+        if indices.iter().enumerate().any(|(i, a)| *a >= self.dice_count || indices[..i].contains(a)) {
+            return false;
+        }
+
+        let mut remaining: Vec<ElementType> = indices.iter().map(|i| self.dices[*i]).collect();
+
+        if let Some((ty, num)) = cost.elemental {
+            // Spend dice of the element before omni dice
+            for element in [ty, Universal] {
+                while remaining.len() > indices.len() - num {
+                    match remaining.iter().position(|a| *a == element) {
+                        Some(index) => { remaining.remove(index); }
+                        None => break,
+                    }
+                }
+            }
+
+            if remaining.len() > indices.len() - num {
+                return false;
+            }
+        }
+
+        if cost.same > 0 {
+            let universal_count = remaining.iter().filter(|a| **a == Universal).count();
+            let has_same = remaining.iter().any(|a| {
+                remaining.iter().filter(|b| *b == a).count() + if *a == Universal { 0 } else { universal_count } >= cost.same
+            });
+
+            if !has_same {
+                return false;
+            }
+        }
+
+        true
+    }
+
     pub fn to_vec(&self) -> Vec<i8> {
         let mut vec = vec![];
 
@@ -139,4 +228,98 @@ impl DiceSet {
 
         vec
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ElementType::*;
+
+    fn dice(elements: &[ElementType]) -> DiceSet {
+        let mut set = DiceSet::default();
+        for element in elements {
+            set.add_dice(*element);
+        }
+        set
+    }
+
+    fn cost(elemental: Option<(ElementType, usize)>, same: usize, unaligned: usize) -> Cost {
+        Cost { elemental, same, unaligned, energy: 0 }
+    }
+
+    #[test]
+    fn elemental_cost_needs_matching_dice() {
+        let set = dice(&[Pyro, Pyro, Hydro]);
+        let cost = cost(Some((Pyro, 2)), 0, 0);
+        assert!(set.check_cost(&[0, 1], &cost));
+        assert!(!set.check_cost(&[0, 2], &cost));
+        assert!(!set.check_cost(&[0], &cost));
+    }
+
+    #[test]
+    fn omni_dice_substitute_any_element() {
+        let set = dice(&[Universal, Pyro, Hydro]);
+        assert!(set.check_cost(&[0, 1], &cost(Some((Pyro, 2)), 0, 0)));
+        assert!(set.check_cost(&[0, 2], &cost(None, 2, 0)));
+        assert!(!set.check_cost(&[1, 2], &cost(None, 2, 0)));
+    }
+
+    #[test]
+    fn duplicate_or_unknown_indices_are_rejected() {
+        let set = dice(&[Pyro, Hydro]);
+        assert!(!set.check_cost(&[0, 0], &cost(Some((Pyro, 2)), 0, 0)));
+        assert!(!set.check_cost(&[0, 5], &cost(None, 0, 2)));
+    }
+
+    #[test]
+    fn same_element_cost() {
+        let set = dice(&[Cryo, Cryo, Cryo, Geo]);
+        assert!(set.check_cost(&[0, 1, 2], &cost(None, 3, 0)));
+        assert!(!set.check_cost(&[0, 1, 3], &cost(None, 3, 0)));
+    }
+
+    #[test]
+    fn unaligned_cost_accepts_anything() {
+        let set = dice(&[Pyro, Geo, Dendro]);
+        assert!(set.check_cost(&[0, 1, 2], &cost(None, 0, 3)));
+    }
+
+    #[test]
+    fn solve_cost_finds_a_valid_payment() {
+        let set = dice(&[Universal, Pyro, Pyro, Hydro, Geo]);
+        let costs = [
+            cost(Some((Pyro, 3)), 0, 0),
+            cost(Some((Pyro, 1)), 0, 2),
+            cost(None, 2, 0),
+            cost(Some((Hydro, 1)), 0, 1),
+        ];
+
+        for cost in costs {
+            let selection = set.solve_cost(&cost).unwrap_or_else(|| panic!("{:?} not solved", cost));
+            assert!(set.check_cost(&selection, &cost), "{:?} paid with {:?}", cost, selection);
+        }
+    }
+
+    #[test]
+    fn solve_cost_keeps_omni_dice_for_last() {
+        let set = dice(&[Universal, Pyro, Hydro]);
+        assert_eq!(set.solve_cost(&cost(Some((Pyro, 1)), 0, 0)), Some(vec![1]));
+    }
+
+    #[test]
+    fn solve_cost_fails_without_enough_dice() {
+        let set = dice(&[Pyro, Hydro]);
+        assert_eq!(set.solve_cost(&cost(Some((Pyro, 2)), 0, 0)), None);
+        assert_eq!(set.solve_cost(&cost(None, 2, 0)), None);
+        assert_eq!(set.solve_cost(&cost(None, 0, 3)), None);
+    }
+
+    #[test]
+    fn sort_puts_omni_then_character_elements_first() {
+        let mut set = dice(&[Geo, Universal, Cryo, Pyro, Universal, Hydro, Pyro]);
+        set.sort_dice(vec![Pyro, Cryo]);
+        assert_eq!(set.dices[0..set.dice_count], [Universal, Universal, Pyro, Pyro, Cryo, Hydro, Geo]);
+        // Unused slots stay at the end
+        assert!(set.dices[set.dice_count..].iter().all(|a| *a == Null));
+    }
+}
@@ -1,169 +1,1060 @@
 use std::sync::{Arc, RwLock};
 use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
 use actix::Addr;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use crate::operation_context::OperationContext;
+use crate::bot::Bot;
+use crate::cards::{SummonedCard, SupportCard};
+use crate::clock::Clocks;
 use crate::characters::character::CharacterHandler;
+use crate::characters::registry::CharacterRegistry;
+use crate::cost::{AppliedModifier, Cost, CostSubject};
+use crate::deck::Deck;
 use crate::dice_set::ElementType;
 use crate::game_events::{GameEvent, SkillType};
-use crate::player::Player;
+use crate::hooks::HookEvent;
+use crate::phase::Phase;
+use crate::player::{Player, OPENING_HAND_SIZE};
 use crate::player_session::PlayerSession;
+use crate::damage::{CharacterRef, DamageEvent, DamageKind, DamageResult, DamageSource};
+use crate::reactions::{apply_element, BurningFlame, CatalyzingField, CrystallizeShield, DamageType, DendroCore, Frozen, Reaction};
+use crate::status::{Status, StatusOwner};
 use crate::server_messages::*;
 
+// Time a disconnected player has to resume the game
+const RECONNECT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
 pub struct GameEnvironment {
     pub players: [Player; 2],
     pub session_addr: [Option<Addr<PlayerSession>>; 2],
+    // Spectator sessions and whether they see hidden information
+    pub spectators: Vec<(Addr<PlayerSession>, bool)>,
     pub active_player_count: usize,
-    pub round_end_count: usize,
+    pub phase: Phase,
+    pub round: usize,
+    // Player whose turn it is during the action phase
+    pub current_turn: usize,
+    // Player acting first in the current round
+    pub first_player: usize,
+    pub declared_end: [bool; 2],
     pub game_ended: Arc<RwLock<bool>>,
+    pub result: Option<GameResult>,
+    // Events held back until players choose new active characters
+    pub deferred_events: Vec<GameEvent>,
+    pub mulligan_done: [bool; 2],
+    // Every random outcome comes from this generator, so a game can be replayed from its seed and events
+    pub seed: u64,
+    pub rng: StdRng,
+    // Events processed so far, in order
+    pub history: Vec<GameEvent>,
+    // When each disconnected player lost their connection
+    pub disconnected_at: [Option<Instant>; 2],
+    pub clocks: Clocks,
+    // Bots playing in place of a session
+    pub bots: [Option<Box<dyn Bot>>; 2],
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum GameResult {
+    Winner(usize),
+    Draw,
 }
 
 impl GameEnvironment {
-    pub fn new() -> Self {
+    pub fn new(registry: &CharacterRegistry, seed: u64) -> Self {
         GameEnvironment {
             players: [
-                Player::new(),
-                Player::new()
+                Player::new(registry, &Deck::default()),
+                Player::new(registry, &Deck::default())
             ],
             session_addr: [None, None],
+            spectators: vec![],
             active_player_count: 0,
-            round_end_count: 0,
+            phase: Phase::Waiting,
+            round: 0,
+            current_turn: 0,
+            first_player: 0,
+            declared_end: [false, false],
             game_ended: Arc::new(RwLock::new(false)),
+            result: None,
+            deferred_events: vec![],
+            mulligan_done: [false, false],
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            history: vec![],
+            disconnected_at: [None, None],
+            clocks: Clocks::default(),
+            bots: [None, None],
         }
     }
 
-    pub fn add_player(&mut self, player_addr: Addr<PlayerSession>) -> usize {
-        self.session_addr[self.active_player_count] = Some(player_addr);
+    // Players without a session are used when replaying a game
+    pub fn add_player(&mut self, player_addr: Option<Addr<PlayerSession>>, player: Player) -> usize {
+        self.session_addr[self.active_player_count] = player_addr;
+        self.players[self.active_player_count] = player;
         self.active_player_count += 1;
         self.active_player_count - 1
     }
 
+    pub fn add_spectator(&mut self, addr: Addr<PlayerSession>, full: bool) {
+        addr.do_send(self.state_view(0, [full, full]));
+        self.spectators.push((addr, full));
+    }
+
+    // Central damage pipeline, every damage dealt to a character goes through here
+    pub fn deal_damage(&mut self, source: DamageSource, target: CharacterRef, amount: usize,
+                       damage_type: DamageType, kind: DamageKind) -> DamageResult {
+        // Summons acting at round end may target an active character defeated earlier in the same round
+        if self.players[target.player].characters[target.character].defeated {
+            return DamageResult {
+                source,
+                target,
+                damage: 0,
+                damage_type,
+                kind,
+                reaction: None,
+                absorbed: 0,
+                remaining_hp: 0,
+            };
+        }
+
+        let mut event = DamageEvent {
+            source,
+            target,
+            amount,
+            damage_type,
+            kind,
+            reaction: None,
+            target_active: self.players[target.player].active_character == target.character,
+            absorbed: 0,
+        };
+
+        // Piercing damage ignores reactions and modifiers
+        if damage_type != DamageType::Piercing {
+            self.resolve_reaction(&mut event);
+
+            let mut statuses = self.statuses_involved(source, target);
+            statuses.sort_by_key(|(_, status)| status.stage());
+            for (owner, status) in statuses.iter() {
+                status.before_damage(*owner, &mut event);
+            }
+
+            for player in self.players.iter_mut() {
+                player.remove_expired_statuses();
+            }
+        }
+
+        let character = &mut self.players[target.player].characters[target.character];
+        character.hp = character.hp.saturating_sub(event.amount);
+
+        let newly_defeated = character.hp == 0 && !character.defeated;
+        if newly_defeated {
+            character.defeated = true;
+            character.applied_elements.clear();
+            character.statuses.clear();
+        }
+
+        let result = DamageResult {
+            source,
+            target,
+            damage: event.amount,
+            damage_type,
+            kind,
+            reaction: event.reaction,
+            absorbed: event.absorbed,
+            remaining_hp: character.hp,
+        };
+
+        for addr in self.session_addr.iter().flatten() {
+            addr.do_send(DamageMessage {
+                result: result.clone(),
+            });
+
+            if newly_defeated {
+                addr.do_send(CharacterDefeatedMessage {
+                    player_index: target.player,
+                    character_index: target.character,
+                });
+            }
+        }
+
+        self.dispatch(HookEvent::Damage(result.clone()));
+        if newly_defeated {
+            self.dispatch(HookEvent::CharacterDefeated(target));
+        }
+
+        if let Some(reaction) = event.reaction {
+            self.apply_reaction_effects(reaction, source, target);
+            self.dispatch(HookEvent::Reaction(reaction, source, target));
+        }
+
+        result
+    }
+
+    fn resolve_reaction(&mut self, event: &mut DamageEvent) {
+        let character = &mut self.players[event.target.player].characters[event.target.character];
+
+        if let DamageType::Elemental(element) = event.damage_type {
+            event.reaction = apply_element(&mut character.applied_elements, element);
+            if let Some(reaction) = event.reaction {
+                event.amount += reaction.bonus_damage();
+            }
+        }
+    }
+
+    // Combat statuses of both players and the statuses of the characters dealing and taking the damage
+    fn statuses_involved(&self, source: DamageSource, target: CharacterRef) -> Vec<(StatusOwner, Arc<dyn Status>)> {
+        let mut statuses = self.players[source.player].all_statuses(source.player);
+        if target.player != source.player {
+            statuses.extend(self.players[target.player].all_statuses(target.player));
+        }
+
+        statuses.into_iter().filter(|(owner, _)| match owner.character {
+            None => true,
+            Some(character) => (owner.player == source.player && Some(character) == source.character)
+                || (owner.player == target.player && character == target.character),
+        }).collect()
+    }
+
+    // Characters of the same player other than the target that are still alive
+    pub fn other_characters(&self, target: CharacterRef) -> Vec<CharacterRef> {
+        (0..self.players[target.player].characters.len())
+            .filter(|i| *i != target.character && !self.players[target.player].characters[*i].defeated)
+            .map(|i| CharacterRef { player: target.player, character: i })
+            .collect()
+    }
+
+    fn apply_reaction_effects(&mut self, reaction: Reaction, source: DamageSource, target: CharacterRef) {
+        let others = self.other_characters(target);
+
+        match reaction {
+            Reaction::Overloaded => {
+                let player = &mut self.players[target.player];
+                if player.active_character == target.character {
+                    if let Some(next) = player.next_alive_character(target.character) {
+                        self.switch_character(target.player, next);
+                    }
+                }
+            }
+
+            Reaction::Superconduct | Reaction::ElectroCharged => {
+                for other in others {
+                    self.deal_damage(source, other, 1, DamageType::Piercing, DamageKind::Reaction);
+                }
+            }
+
+            Reaction::Frozen => {
+                self.players[target.player].characters[target.character].add_status(Arc::new(Frozen::new()));
+            }
+
+            Reaction::Swirl(element) => {
+                for other in others {
+                    self.deal_damage(source, other, 1, DamageType::Elemental(element), DamageKind::Reaction);
+                }
+            }
+
+            Reaction::Crystallize(_) => {
+                self.players[source.player].add_combat_status(Arc::new(CrystallizeShield::new()));
+            }
+
+            Reaction::Bloom => {
+                self.players[source.player].add_combat_status(Arc::new(DendroCore::new()));
+            }
+
+            Reaction::Quicken => {
+                self.players[source.player].add_combat_status(Arc::new(CatalyzingField::new()));
+            }
+
+            Reaction::Burning => {
+                self.summon(source.player, Arc::new(BurningFlame::new()));
+            }
+
+            Reaction::Melt | Reaction::Vaporize => {}
+        }
+    }
+
+    pub fn switch_character(&mut self, id: usize, to: usize) {
+        let player = &mut self.players[id];
+        let from = player.active_character;
+        player.active_character = to;
+
+        // Dice of the new active character's element are sorted first
+        let player_elements = player.get_character_elements();
+        player.dice_set.sort_dice(player_elements);
+        self.update_dices(id);
+
+        for addr in self.session_addr.iter().flatten() {
+            addr.do_send(CharacterSwitchedMessage {
+                player_index: id,
+                from,
+                to,
+            });
+        }
+
+        self.dispatch(HookEvent::CharacterSwitched(id, from, to));
+    }
+
+    pub fn switch_cost(&self, id: usize) -> (Cost, Vec<AppliedModifier>) {
+        // Switching costs one die of any element
+        self.players[id].effective_cost(id, CostSubject::ChangeActive, &Cost::unaligned(1))
+    }
+
+    // Uses up a fast switch effect of the player's team or active character if there is one
+    fn consume_fast_switch(&mut self, id: usize) -> bool {
+        let active_character = self.players[id].active_character;
+        let fast_switch = self.players[id].all_statuses(id).into_iter().find(|(owner, status)| {
+            (owner.character.is_none() || owner.character == Some(active_character)) && status.fast_switch(*owner)
+        });
+
+        match fast_switch {
+            Some((_, status)) => {
+                status.counter().consume();
+                self.players[id].remove_expired_statuses();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn summon(&mut self, player: usize, card: Arc<dyn SummonedCard>) {
+        let player = &mut self.players[player];
+        if let Some(existing) = player.find_summoned(card.name()) {
+            existing.refresh();
+        } else if player.summoned_area_count < player.summoned_area.len() {
+            player.insert_summoned(card);
+        }
+    }
+
+    // The oldest support is discarded when the support area is full
+    pub fn add_support(&mut self, player: usize, card: Arc<dyn SupportCard>) {
+        let player_ref = &mut self.players[player];
+        if player_ref.support_area_count == player_ref.support_area.len() {
+            player_ref.remove_support(0);
+        }
+
+        player_ref.insert_support(card.clone());
+        card.on_created(player, self);
+    }
+
+    // Checks for defeated characters after an action is resolved
+    // Returns false if the game cannot go on until new active characters are chosen
+    fn resolve_defeats(&mut self) -> bool {
+        let all_defeated = [self.players[0].all_defeated(), self.players[1].all_defeated()];
+        match all_defeated {
+            [true, true] => {
+                self.end_game(GameResult::Draw);
+                return false;
+            }
+            [true, false] => {
+                self.end_game(GameResult::Winner(1));
+                return false;
+            }
+            [false, true] => {
+                self.end_game(GameResult::Winner(0));
+                return false;
+            }
+            _ => {}
+        }
+
+        for index in 0..2usize {
+            let player = &mut self.players[index];
+            if player.characters[player.active_character].defeated && !player.choosing_active {
+                player.choosing_active = true;
+
+                if let Some(addr) = self.session_addr[index].as_ref() {
+                    addr.do_send(ChooseActiveMessage {
+                        available_characters: self.players[index].alive_characters(),
+                    });
+                }
+            }
+        }
+
+        !self.players.iter().any(|a| a.choosing_active)
+    }
+
+    // Sends the event to the game loop, or holds it back while a player is choosing a new active character
+    fn proceed(&mut self, event: GameEvent, send: &Sender<GameEvent>) {
+        if self.resolve_defeats() {
+            send.send(event).expect("Game loop has stopped");
+        } else if self.result.is_none() {
+            self.deferred_events.push(event);
+        }
+    }
+
+    fn update_dices(&self, index: usize) {
+        if let Some(addr) = self.session_addr[index].as_ref() {
+            addr.do_send(UpdateDicesMessage {
+                dice_set: self.players[index].dice_set.to_vec(),
+            });
+        }
+    }
+
+    pub fn skill_cost(&self, id: usize, skill: SkillType) -> (Cost, Vec<AppliedModifier>) {
+        let player = &self.players[id];
+        let character = &player.characters[player.active_character];
+        player.effective_cost(id, CostSubject::Skill(player.active_character, skill), character.skill_cost(skill))
+    }
+
+    pub fn card_cost(&self, id: usize, card: usize) -> (Cost, Vec<AppliedModifier>) {
+        let player = &self.players[id];
+        player.effective_cost(id, CostSubject::ActionCard, &player.hand[card].cost())
+    }
+
+    // Uses a skill of the active character, dice are paid by the caller
+    // The energy cost has to be checked beforehand
+    pub fn use_skill(&mut self, id: usize, skill: SkillType, energy_cost: usize) {
+        let active_character = self.players[id].active_character;
+        let raw_handler =
+            Arc::into_raw(self.players[id].characters[active_character].handler.clone())
+                as *mut dyn CharacterHandler;
+
+        let context_info = OperationContext::new(
+            id,
+            active_character,
+            self.players[1 - id].active_character,
+        );
+
+        let character = &mut self.players[id].characters[active_character];
+        // Bursts consume energy, other skills charge one point
+        character.energy -= energy_cost;
+        if skill != SkillType::QSkill {
+            character.gain_energy(1);
+        }
+
+        let character_ref = CharacterRef { player: id, character: active_character };
+        self.dispatch(HookEvent::BeforeSkill(character_ref, skill));
+
+        // This is safe because handler will only be read in one thread
+        unsafe {
+            match skill {
+                SkillType::NormalAttack => {
+                    (*raw_handler).on_normal_attack(context_info, self);
+                }
+                SkillType::ESkill => {
+                    (*raw_handler).on_e_skill(context_info, self);
+                }
+                SkillType::QSkill => {
+                    (*raw_handler).on_q_skill(context_info, self);
+                }
+            }
+        }
+
+        self.dispatch(HookEvent::AfterSkill(character_ref, skill));
+
+        self.update_energy(id);
+    }
+
+    // Removes the dice at the given indices from the player's dice set
+    pub fn spend_dice(&mut self, id: usize, dices: &[usize]) {
+        let player = &mut self.players[id];
+        for i in dices.iter() {
+            player.dice_set.dices[*i] = ElementType::Null;
+        }
+
+        let player_elements = player.get_character_elements();
+        player.dice_set.sort_dice(player_elements);
+        player.dice_set.dice_count -= dices.len();
+        self.update_dices(id);
+    }
+
+    // Selects dice for the player when an action that needs dice is sent without any
+    pub fn complete_dice_selection(&self, msg: &GameEvent) -> GameEvent {
+        match msg {
+            GameEvent::UseSkill(id, skill, dices) if dices.is_empty() => {
+                let (cost, _) = self.skill_cost(*id, *skill);
+                let dices = self.players[*id].dice_set.solve_cost(&cost).unwrap_or_default();
+                GameEvent::UseSkill(*id, *skill, dices)
+            }
+
+            GameEvent::ChangeActive(id, t, dices) if dices.is_empty() => {
+                let (cost, _) = self.switch_cost(*id);
+                let dices = self.players[*id].dice_set.solve_cost(&cost).unwrap_or_default();
+                GameEvent::ChangeActive(*id, *t, dices)
+            }
+
+            GameEvent::UseActionCard(id, card, target, dices)
+                if dices.is_empty() && *card < self.players[*id].hand.len() => {
+                let (cost, _) = self.card_cost(*id, *card);
+                let dices = self.players[*id].dice_set.solve_cost(&cost).unwrap_or_default();
+                GameEvent::UseActionCard(*id, *card, *target, dices)
+            }
+
+            _ => msg.clone(),
+        }
+    }
+
+    pub fn update_hand(&self, index: usize) {
+        if let Some(addr) = self.session_addr[index].as_ref() {
+            addr.do_send(UpdateHandMessage {
+                cards: self.players[index].hand.iter().map(|a| a.name().to_string()).collect(),
+            });
+        }
+    }
+
+    pub fn update_statuses(&self, index: usize) {
+        let player = &self.players[index];
+        for addr in self.session_addr.iter().flatten() {
+            addr.do_send(UpdateStatusesMessage {
+                player_index: index,
+                combat_statuses: player.combat_statuses.iter().map(|a| a.info()).collect(),
+                character_statuses: player.characters.iter().map(|a| {
+                    a.statuses.iter().map(|b| b.info()).collect()
+                }).collect(),
+            });
+        }
+    }
+
+    fn update_all_statuses(&self) {
+        for index in 0..2usize {
+            self.update_statuses(index);
+        }
+    }
+
+    pub fn update_energy(&self, index: usize) {
+        for addr in self.session_addr.iter().flatten() {
+            addr.do_send(UpdateEnergyMessage {
+                player_index: index,
+                energy: self.players[index].characters.iter().map(|a| a.energy).collect(),
+            });
+        }
+    }
+
+    // Passes the turn after a combat action, players who declared round end are skipped
+    fn end_turn(&mut self, id: usize, send: &Sender<GameEvent>) {
+        let next = if self.declared_end[1 - id] { id } else { 1 - id };
+        self.current_turn = next;
+        self.proceed(GameEvent::TurnOf(next), send);
+    }
+
+    fn end_game(&mut self, result: GameResult) {
+        self.result = Some(result);
+        self.phase = Phase::GameOver;
+        *self.game_ended.write().unwrap() = true;
+
+        for (index, addr) in self.session_addr.iter().enumerate() {
+            let outcome = match result {
+                GameResult::Draw => GameOutcome::Draw,
+                GameResult::Winner(winner) if winner == index => GameOutcome::Win,
+                GameResult::Winner(_) => GameOutcome::Loss,
+            };
+
+            if let Some(addr) = addr {
+                addr.do_send(GameOverMessage { outcome });
+            }
+        }
+    }
+
+    // Called by the game loop once per tick
+    pub fn tick(&mut self, send: &Sender<GameEvent>) {
+        for index in 0..2usize {
+            let expired = self.disconnected_at[index].is_some_and(|a| a.elapsed() >= RECONNECT_GRACE_PERIOD);
+            // Rooms that never started are cleaned up by the server instead
+            if expired && self.result.is_none() && self.phase != Phase::Waiting {
+                println!("Player {} did not reconnect in time", index);
+                self.disconnected_at[index] = None;
+                send.send(GameEvent::Concede(index)).expect("Game loop has stopped");
+            }
+        }
+
+        self.update_clocks(send);
+        self.run_bots(send);
+    }
+
+    fn setup_client(&self, id: usize) {
+        if let Some(addr) = self.session_addr[id].as_ref() {
+            addr.do_send(SetupClientMessage {
+                player_index: id,
+                player_characters: self.players[id].characters.iter().map(|a| {
+                    a.name.to_string()
+                }).collect(),
+                opponent_characters: self.players[1usize - id].characters.iter().map(|a| {
+                    a.name.to_string()
+                }).collect(),
+            });
+        }
+    }
+
+    fn update_connection(&self, index: usize, connected: bool) {
+        if let Some(addr) = self.session_addr[1 - index].as_ref() {
+            addr.do_send(ConnectionStatusMessage {
+                player_index: index,
+                connected,
+                grace_period: RECONNECT_GRACE_PERIOD.as_secs(),
+            });
+        }
+    }
+
     pub fn handle_message(&mut self, msg: &GameEvent, send: &Sender<GameEvent>) {
+        let msg = &self.complete_dice_selection(msg);
+        if let Err(reason) = self.validate(msg) {
+            println!("Event rejected: {:?}", reason);
+            if let Some(addr) = msg.player().and_then(|id| self.session_addr[id].as_ref()) {
+                addr.do_send(ActionRejectedMessage { reason });
+            }
+            return;
+        }
+
+        self.history.push(msg.clone());
+        if let Some(id) = msg.player().filter(|_| !msg.is_connection_event()) {
+            self.clocks.reset_action(id);
+        }
+
         match msg {
             GameEvent::SetupClient(id) => {
-                self.session_addr[*id].as_ref().unwrap().do_send(SetupClientMessage {
-                    player_index: *id,
-                    player_characters: self.players[*id].characters.iter().map(|a| {
-                        a.name.to_string()
-                    }).collect(),
-                    opponent_characters: self.players[1usize - id].characters.iter().map(|a| {
-                        a.name.to_string()
-                    }).collect(),
-                });
+                self.setup_client(*id);
+
+                if self.phase == Phase::Waiting && self.active_player_count == 2 {
+                    // The first player was set up before the opponent's deck was known
+                    self.setup_client(1 - *id);
+                    self.phase = Phase::Mulligan;
+                    // Drawn from the game's generator so replays start with the same player
+                    self.first_player = self.rng.gen_range(0..2);
+                    for index in 0..2usize {
+                        self.players[index].shuffle_draw_pile(&mut self.rng);
+                        self.players[index].draw_cards(OPENING_HAND_SIZE);
+                        self.update_hand(index);
+                    }
+                }
+            }
+
+            GameEvent::ChangeActive(id, t, dices) => {
+                let (_, applied_modifiers) = self.switch_cost(*id);
+                self.players[*id].consume_cost_modifiers(applied_modifiers);
+                self.spend_dice(*id, dices);
+
+                let fast_switch = self.consume_fast_switch(*id);
+                self.switch_character(*id, *t);
+                self.update_all_statuses();
+
+                if fast_switch {
+                    self.proceed(GameEvent::TurnOf(*id), send);
+                } else {
+                    self.end_turn(*id, send);
+                }
             }
 
-            GameEvent::ChangeActive(id, t) => {
-                self.players[*id].active_character = *t;
+            GameEvent::UseActionCard(id, card, target, dices) => {
+                let (_, applied_modifiers) = self.card_cost(*id, *card);
+                self.players[*id].consume_cost_modifiers(applied_modifiers);
+
+                let card = self.players[*id].hand.remove(*card);
+                self.spend_dice(*id, dices);
+
+                for addr in self.session_addr.iter().flatten() {
+                    addr.do_send(CardPlayedMessage {
+                        player_index: *id,
+                        card: card.name().to_string(),
+                    });
+                }
+
+                let context_info = OperationContext::new(*id, *target, self.players[1 - id].active_character);
+                card.use_card(context_info, self);
+                self.dispatch(HookEvent::CardPlayed(*id, card.name()));
+                self.update_hand(*id);
+                self.update_all_statuses();
+
+                if card.is_combat_action() {
+                    self.end_turn(*id, send);
+                } else {
+                    self.proceed(GameEvent::TurnOf(*id), send);
+                }
             }
 
-            GameEvent::UseActionCard(id, card, target) => {
-                // self.player.use_card(*target, self);
+            GameEvent::SwapCards(id, cards) => {
+                self.players[*id].swap_cards(cards, &mut self.rng);
+                self.update_hand(*id);
+
+                self.mulligan_done[*id] = true;
+                if self.mulligan_done.iter().all(|a| *a) {
+                    send.send(GameEvent::RoundStart).expect("Game loop has stopped");
+                }
             }
 
             GameEvent::DeclareRoundEnd(id) => {
-                self.round_end_count += 1;
-                if self.round_end_count == self.active_player_count {
-                    send.send(GameEvent::RoundEnd).expect("TODO: panic message");
+                // Whoever declares round end first acts first in the next round
+                if !self.declared_end[1 - id] {
+                    self.first_player = *id;
+                }
+
+                self.declared_end[*id] = true;
+                if self.declared_end.iter().all(|a| *a) {
+                    self.phase = Phase::End;
+                    send.send(GameEvent::RoundEnd).expect("Game loop has stopped");
+                } else {
+                    self.end_turn(*id, send);
                 }
             }
 
             GameEvent::RoundEnd => {
-                for index in 0..2usize {
-                    let summoned_area = self.players[index].summoned_area.clone();
-                    for i in 0..summoned_area.len() {
-                        summoned_area[i].on_turn_end(index, self);
+                self.phase = Phase::End;
+                self.dispatch(HookEvent::RoundEnd(self.round));
 
-                        if summoned_area[i].remaining_uses() == 0 {
+                for index in 0..2usize {
+                    for i in (0..self.players[index].summoned_area_count).rev() {
+                        if self.players[index].summoned_area[i].remaining_uses() == 0 {
                             self.players[index].remove_summoned(i);
                         }
                     }
 
-                    let support_area = self.players[index].support_area.clone();
-                    for i in support_area.iter() {
-                        i.on_turn_end(index, self);
+                    for (_, status) in self.players[index].all_statuses(index) {
+                        status.counter().pass_round();
                     }
+                    self.players[index].remove_expired_statuses();
+
+                    self.players[index].draw_cards(2);
+                    self.update_hand(index);
                 }
 
-                self.round_end_count = 0;
-                send.send(GameEvent::RoundStart).expect("TODO: panic message");
+                self.update_all_statuses();
+                self.proceed(GameEvent::RoundStart, send);
             }
 
             GameEvent::RoundStart => {
+                self.round += 1;
+                self.phase = Phase::Roll;
+                self.clocks.reset_round();
+                self.declared_end = [false, false];
+                self.current_turn = self.first_player;
+
                 for index in 0..2usize {
                     self.players[index].reroll_chances = 1;
 
-                    self.players[index].dice_set.roll_dices();
+                    self.players[index].dice_set.roll_dices(&mut self.rng);
                     let player_elements = self.players[index].get_character_elements();
                     self.players[index].dice_set.sort_dice(player_elements);
-
-                    let support_area = self.players[index].support_area.clone();
-                    for i in support_area.iter() {
-                        i.on_turn_start(index, self);
-                    }
-
-                    self.session_addr[index].as_ref().unwrap().do_send(UpdateDicesMessage{
-                        dice_set: self.players[index].dice_set.to_vec(),
-                    });
+                    self.dispatch(HookEvent::DiceRolled(index, false));
                 }
-            }
 
-            GameEvent::UseSkill(id, skill, cost) => {
-                let raw_handler =
-                    Arc::into_raw(self.players[*id].characters[self.players[*id].active_character].handler.clone())
-                        as *mut dyn CharacterHandler;
-
-                let context_info = OperationContext::new(
-                    *id,
-                    self.players[*id].active_character,
-                    self.players[1 - id].active_character,
-                );
-
-                // This is safe because handler will only be read in one thread
-                unsafe {
-                    match skill {
-                        SkillType::NormalAttack => {
-                            (*raw_handler).on_normal_attack(context_info, self);
-                        }
-                        SkillType::ESkill => {
-                            (*raw_handler).on_e_skill(context_info, self);
-                        }
-                        SkillType::QSkill => {
-                            (*raw_handler).on_q_skill(context_info, self);
+                self.dispatch(HookEvent::RoundStart(self.round));
+                for index in 0..2usize {
+                    for i in (0..self.players[index].support_area_count).rev() {
+                        if self.players[index].support_area[i].remaining_uses() == 0 {
+                            self.players[index].remove_support(i);
                         }
                     }
-                }
 
-                for i in cost.iter() {
-                    self.players[*id].dice_set.dices[*i] = ElementType::Null;
+                    self.update_dices(index);
                 }
 
-                let player_elements = self.players[*id].get_character_elements();
-                self.players[*id].dice_set.sort_dice(player_elements);
-                self.players[*id].dice_set.dice_count -= cost.len();
+                self.phase = Phase::Reroll;
+            }
 
-                send.send(GameEvent::TurnOf(1 - id)).expect("TODO: panic message");
+            GameEvent::UseSkill(id, skill, dices) => {
+                // The same cost the action was validated against
+                let (cost, applied_modifiers) = self.skill_cost(*id, *skill);
+                self.players[*id].consume_cost_modifiers(applied_modifiers);
+
+                // Paid before the skill runs, its effects may change the dice
+                self.spend_dice(*id, dices);
+                self.use_skill(*id, *skill, cost.energy);
+                self.update_all_statuses();
+                self.end_turn(*id, send);
             }
 
             GameEvent::RerollDice(id, dices) => {
-                if dices.len() == 0 {
+                if dices.is_empty() {
                     self.players[*id].reroll_chances = 0;
                 } else {
                     for i in dices.iter() {
-                        self.players[*id].dice_set.reroll_dice(*i);
+                        self.players[*id].dice_set.reroll_dice(*i, &mut self.rng);
                     }
 
                     let player_elements = self.players[*id].get_character_elements();
                     self.players[*id].dice_set.sort_dice(player_elements);
                     self.players[*id].reroll_chances -= 1;
+                    self.dispatch(HookEvent::DiceRolled(*id, true));
+                    self.update_dices(*id);
+                }
+
+                if self.players.iter().all(|a| a.reroll_chances == 0) {
+                    self.phase = Phase::Action;
+                    send.send(GameEvent::TurnOf(self.first_player)).expect("Game loop has stopped");
                 }
             }
             
+            GameEvent::ChooseActive(id, t) => {
+                self.players[*id].choosing_active = false;
+                self.switch_character(*id, *t);
+
+                if !self.players.iter().any(|a| a.choosing_active) {
+                    for event in self.deferred_events.drain(..) {
+                        send.send(event).expect("Game loop has stopped");
+                    }
+                }
+            }
+
+            GameEvent::Tune(id, card, dice) => {
+                let player = &mut self.players[*id];
+                player.hand.remove(*card);
+
+                let element = player.characters[player.active_character].element;
+                player.dice_set.tune_dice(*dice, element);
+                let player_elements = player.get_character_elements();
+                player.dice_set.sort_dice(player_elements);
+
+                self.update_hand(*id);
+                self.update_dices(*id);
+                self.proceed(GameEvent::TurnOf(*id), send);
+            }
+
+            // The game goes on while a player is away, the player forfeits if they do not come back in time
+            GameEvent::Disconnected(id) => {
+                self.session_addr[*id] = None;
+                self.disconnected_at[*id] = Some(Instant::now());
+                self.update_connection(*id, false);
+            }
+
+            // The session was already rebound by the server, the client gets everything it needs to rebuild the game
+            GameEvent::Reconnected(id) => {
+                self.disconnected_at[*id] = None;
+                self.setup_client(*id);
+                self.update_hand(*id);
+                self.update_dices(*id);
+
+                let player = &self.players[*id];
+                if player.choosing_active {
+                    if let Some(addr) = self.session_addr[*id].as_ref() {
+                        addr.do_send(ChooseActiveMessage {
+                            available_characters: player.alive_characters(),
+                        });
+                    }
+                }
+
+                self.update_connection(*id, true);
+            }
+
+            GameEvent::Concede(id) => {
+                self.end_game(GameResult::Winner(1 - id));
+            }
+
             GameEvent::TurnOf(id) => {
-                for elem in self.session_addr.iter(){
-                    elem.as_ref().unwrap().do_send(TurnOfMessage{
+                for elem in self.session_addr.iter().flatten() {
+                    elem.do_send(TurnOfMessage {
                         turn_of: *id,
                     });
                 }
             }
         }
+
+        self.broadcast_state();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::mpsc::channel;
+    use super::*;
+    use crate::characters::registry::CHARACTER_DATA_DIR;
+    use crate::damage::ModifierStage;
+    use crate::status::StatusCounter;
+    use crate::validation::RejectReason;
+
+    // Handles the event and every event the game sends to itself in response
+    pub fn run(env: &mut GameEnvironment, event: GameEvent) {
+        let (send, recv) = channel();
+        env.handle_message(&event, &send);
+        while let Ok(event) = recv.try_recv() {
+            env.handle_message(&event, &send);
+        }
+    }
+
+    // Game of two default decks waiting for the mulligan
+    pub fn new_game(seed: u64) -> GameEnvironment {
+        let registry = CharacterRegistry::load(CHARACTER_DATA_DIR).unwrap();
+        let mut env = GameEnvironment::new(&registry, seed);
+        env.add_player(None, Player::new(&registry, &Deck::default()));
+        env.add_player(None, Player::new(&registry, &Deck::default()));
+        run(&mut env, GameEvent::SetupClient(0));
+        run(&mut env, GameEvent::SetupClient(1));
+        env
+    }
+
+    // Game in the first action phase, both players kept their cards and dice
+    pub fn action_phase(seed: u64) -> GameEnvironment {
+        let mut env = new_game(seed);
+        run(&mut env, GameEvent::SwapCards(0, vec![]));
+        run(&mut env, GameEvent::SwapCards(1, vec![]));
+        run(&mut env, GameEvent::RerollDice(0, vec![]));
+        run(&mut env, GameEvent::RerollDice(1, vec![]));
+        assert_eq!(env.phase, Phase::Action);
+        env
+    }
+
+    // Leaves the player with only omni dice, enough for any action
+    pub fn omni_dice(env: &mut GameEnvironment, player: usize) {
+        let dice_set = &mut env.players[player].dice_set;
+        dice_set.dice_count = 0;
+        for _ in 0..8 {
+            dice_set.add_dice(ElementType::Universal);
+        }
+    }
+
+    // Changes damage dealt or taken by the owner's team
+    struct Modifier {
+        stage: ModifierStage,
+        apply: fn(usize) -> usize,
+        counter: StatusCounter,
+    }
+
+    impl Status for Modifier {
+        fn name(&self) -> &'static str {
+            "Modifier"
+        }
+
+        fn counter(&self) -> &StatusCounter {
+            &self.counter
+        }
+
+        fn stage(&self) -> ModifierStage {
+            self.stage
+        }
+
+        fn before_damage(&self, _owner: StatusOwner, event: &mut DamageEvent) {
+            event.amount = (self.apply)(event.amount);
+        }
+    }
+
+    fn modifier(stage: ModifierStage, apply: fn(usize) -> usize) -> Arc<dyn Status> {
+        Arc::new(Modifier { stage, apply, counter: StatusCounter::new(StatusCounter::UNLIMITED, StatusCounter::UNLIMITED) })
+    }
+
+    #[test]
+    fn modifiers_apply_by_stage() {
+        let mut env = new_game(1);
+        // Added in reverse order, the stage decides the order they apply in
+        env.players[1].add_combat_status(modifier(ModifierStage::Reduction, |a| a - 1));
+        env.players[0].add_combat_status(modifier(ModifierStage::Multiplicative, |a| a * 2));
+        env.players[0].characters[0].add_status(modifier(ModifierStage::Additive, |a| a + 1));
+
+        let source = DamageSource { player: 0, character: Some(0) };
+        let target = CharacterRef { player: 1, character: 0 };
+        let result = env.deal_damage(source, target, 2, DamageType::Physical, DamageKind::NormalAttack);
+        assert_eq!(result.damage, 5);
+        assert_eq!(result.remaining_hp, 5);
+    }
+
+    #[test]
+    fn last_defeat_ends_the_game() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+
+        let opponent = &mut env.players[1 - id];
+        let active = opponent.active_character;
+        for (i, character) in opponent.characters.iter_mut().enumerate() {
+            if i == active {
+                character.hp = 1;
+            } else {
+                character.hp = 0;
+                character.defeated = true;
+            }
+        }
+
+        run(&mut env, GameEvent::UseSkill(id, SkillType::NormalAttack, vec![]));
+        assert!(env.players[1 - id].all_defeated());
+        assert_eq!(env.result, Some(GameResult::Winner(id)));
+        assert_eq!(env.phase, Phase::GameOver);
+        assert!(*env.game_ended.read().unwrap());
+    }
+
+    #[test]
+    fn defeated_active_character_is_replaced_first() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+        let active = env.players[1 - id].active_character;
+        env.players[1 - id].characters[active].hp = 1;
+
+        run(&mut env, GameEvent::UseSkill(id, SkillType::NormalAttack, vec![]));
+        assert!(env.result.is_none());
+        assert!(env.players[1 - id].choosing_active);
+
+        // Nobody can act until the new active character is chosen
+        omni_dice(&mut env, 1 - id);
+        assert!(env.validate(&GameEvent::DeclareRoundEnd(1 - id)).is_err());
+        assert!(env.validate(&GameEvent::ChooseActive(1 - id, active)).is_err());
+
+        let next = env.players[1 - id].next_alive_character(active).unwrap();
+        run(&mut env, GameEvent::ChooseActive(1 - id, next));
+        assert!(!env.players[1 - id].choosing_active);
+        assert_eq!(env.players[1 - id].active_character, next);
+    }
+
+    #[test]
+    fn phases_of_a_round() {
+        let mut env = new_game(1);
+        assert_eq!(env.phase, Phase::Mulligan);
+        run(&mut env, GameEvent::SwapCards(0, vec![]));
+        assert_eq!(env.phase, Phase::Mulligan);
+        run(&mut env, GameEvent::SwapCards(1, vec![]));
+        assert_eq!(env.phase, Phase::Reroll);
+        assert_eq!(env.round, 1);
+
+        run(&mut env, GameEvent::RerollDice(0, vec![]));
+        run(&mut env, GameEvent::RerollDice(1, vec![]));
+        assert_eq!(env.phase, Phase::Action);
+        assert_eq!(env.current_turn, env.first_player);
+
+        let first = env.first_player;
+        run(&mut env, GameEvent::DeclareRoundEnd(first));
+        assert_eq!(env.current_turn, 1 - first);
+        run(&mut env, GameEvent::DeclareRoundEnd(1 - first));
+        assert_eq!(env.round, 2);
+        assert_eq!(env.phase, Phase::Reroll);
+    }
+
+    #[test]
+    fn first_to_declare_the_round_end_goes_first() {
+        let mut env = action_phase(1);
+        let first = env.first_player;
+        omni_dice(&mut env, first);
+
+        // The second player declares first while the first player still acts
+        run(&mut env, GameEvent::UseSkill(first, SkillType::NormalAttack, vec![]));
+        assert_eq!(env.current_turn, 1 - first);
+        run(&mut env, GameEvent::DeclareRoundEnd(1 - first));
+        assert_eq!(env.current_turn, first);
+        run(&mut env, GameEvent::DeclareRoundEnd(first));
+
+        assert_eq!(env.round, 2);
+        assert_eq!(env.first_player, 1 - first);
+        run(&mut env, GameEvent::RerollDice(0, vec![]));
+        run(&mut env, GameEvent::RerollDice(1, vec![]));
+        assert_eq!(env.current_turn, 1 - first);
+    }
+
+    #[test]
+    fn switching_costs_a_die_and_ends_the_turn() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+        let next = env.players[id].next_alive_character(env.players[id].active_character).unwrap();
+
+        run(&mut env, GameEvent::ChangeActive(id, next, vec![]));
+        assert_eq!(env.players[id].active_character, next);
+        assert_eq!(env.players[id].dice_set.dice_count, 7);
+        assert_eq!(env.current_turn, 1 - id);
+    }
+
+    #[test]
+    fn cannot_switch_to_defeated_or_active_characters() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+        let active = env.players[id].active_character;
+        let next = env.players[id].next_alive_character(active).unwrap();
+        env.players[id].characters[next].hp = 0;
+        env.players[id].characters[next].defeated = true;
+
+        assert_eq!(env.validate(&GameEvent::ChangeActive(id, next, vec![0])), Err(RejectReason::CharacterDefeated));
+        assert_eq!(env.validate(&GameEvent::ChangeActive(id, active, vec![0])), Err(RejectReason::InvalidCharacter));
+        assert_eq!(env.validate(&GameEvent::ChangeActive(id, 3, vec![0])), Err(RejectReason::InvalidCharacter));
+
+        run(&mut env, GameEvent::ChangeActive(id, next, vec![0]));
+        assert_eq!(env.players[id].active_character, active);
+        assert_eq!(env.current_turn, id);
     }
 }
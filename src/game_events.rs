@@ -1,4 +1,6 @@
-#[derive(PartialEq, Eq, Copy, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum SkillType {
     NormalAttack,
     ESkill,
@@ -6,11 +8,12 @@ pub enum SkillType {
 }
 
 // Messages received from the client side
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum GameEvent {
     // Player index
     SetupClient(usize),
-    // Player index, Character index
-    ChangeActive(usize, usize),
+    // Player index, Character index, Dices used
+    ChangeActive(usize, usize, Vec<usize>),
     RoundStart,
     // Player index
     DeclareRoundEnd(usize),
@@ -19,8 +22,48 @@ pub enum GameEvent {
     TurnOf(usize),
     // Player index, Skill, Dices used
     UseSkill(usize, SkillType, Vec<usize>),
-    // Player index, Card index, Character index
-    UseActionCard(usize, usize, usize),
+    // Player index, Card index, Character index, Dices used
+    UseActionCard(usize, usize, usize, Vec<usize>),
     // Player index, Dices to reroll
     RerollDice(usize, Vec<usize>),
-}
\ No newline at end of file
+    // Player index, Character index, sent after the active character is defeated
+    ChooseActive(usize, usize),
+    // Player index, Cards in hand to swap
+    SwapCards(usize, Vec<usize>),
+    // Player index
+    Concede(usize),
+    // Player index, Card discarded, Dice converted
+    Tune(usize, usize, usize),
+    // Player index, sent when the player's session is closed or resumed
+    Disconnected(usize),
+    Reconnected(usize),
+}
+
+impl GameEvent {
+    // Combat actions end the turn of the player performing them
+    pub fn is_combat_action(&self) -> bool {
+        matches!(self, GameEvent::ChangeActive(..) | GameEvent::DeclareRoundEnd(_) | GameEvent::UseSkill(..))
+    }
+
+    // Fast actions can be performed without ending the turn
+    pub fn is_fast_action(&self) -> bool {
+        matches!(self, GameEvent::UseActionCard(..) | GameEvent::Tune(..))
+    }
+
+    // Connection events are accepted at any time
+    pub fn is_connection_event(&self) -> bool {
+        matches!(self, GameEvent::Disconnected(_) | GameEvent::Reconnected(_))
+    }
+
+    // Player performing the event, None for events generated by the server
+    pub fn player(&self) -> Option<usize> {
+        match self {
+            GameEvent::SetupClient(id) | GameEvent::ChangeActive(id, _, _) | GameEvent::DeclareRoundEnd(id)
+            | GameEvent::UseSkill(id, _, _) | GameEvent::UseActionCard(id, _, _, _)
+            | GameEvent::RerollDice(id, _) | GameEvent::ChooseActive(id, _) | GameEvent::SwapCards(id, _)
+            | GameEvent::Concede(id) | GameEvent::Tune(id, _, _)
+            | GameEvent::Disconnected(id) | GameEvent::Reconnected(id) => Some(*id),
+            GameEvent::RoundStart | GameEvent::RoundEnd | GameEvent::TurnOf(_) => None,
+        }
+    }
+}
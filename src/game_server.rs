@@ -1,88 +1,608 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use std::sync::mpsc::{channel, Sender};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
 use std::thread;
-use actix::{Actor, Addr, Context, Handler, Message, MessageResponse};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use actix::{Actor, Addr, AsyncContext, Context, Handler, Message, MessageResponse};
+use rand::prelude::*;
+use serde::Serialize;
+use crate::bot::{create_bot, BotKind};
+use crate::characters::registry::CharacterRegistry;
+use crate::deck::{Deck, DeckError};
+use crate::clock::Clocks;
 use crate::game_environment::GameEnvironment;
 use crate::game_events::GameEvent;
+use crate::lobby::{RoomInfo, RoomOptions, Ruleset};
+use crate::player::Player;
 use crate::player_session::PlayerSession;
+use crate::replay::Replay;
+use crate::server_messages::SessionReplacedMessage;
+
+// How often timers are checked
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(10);
+// Finished rooms are kept for a while so clients can still see the result
+const FINISHED_ROOM_TIMEOUT: Duration = Duration::from_secs(60);
+// Longer than the reconnect grace period, so a player who lost the connection can come back
+const ABANDONED_ROOM_TIMEOUT: Duration = Duration::from_secs(120);
+// Full spectators of a running game see both hands, only late enough to not help either player
+const MIN_FULL_SPECTATOR_DELAY: Duration = Duration::from_secs(30);
 
 pub struct EnterRoomMessage {
     pub addr: Addr<PlayerSession>,
     pub room_id: usize,
+    pub deck: Deck,
+    pub password: Option<String>,
+}
+
+// Creates a room and enters it as the first player
+pub struct CreateRoomMessage {
+    pub addr: Addr<PlayerSession>,
+    pub options: RoomOptions,
+    pub deck: Deck,
+    // Makes the game reproducible, only accepted for practice rooms
+    pub seed: Option<u64>,
+}
+
+impl Message for CreateRoomMessage {
+    type Result = Result<EnterRoomResult, JoinError>;
+}
+
+pub struct ListRoomsMessage;
+
+impl Message for ListRoomsMessage {
+    type Result = Vec<RoomInfo>;
+}
+
+// Waits for another player in the quick-match queue
+pub struct QuickMatchMessage {
+    pub addr: Addr<PlayerSession>,
+    pub deck: Deck,
+}
+
+impl Message for QuickMatchMessage {
+    type Result = Result<(), JoinError>;
+}
+
+pub struct LeaveQueueMessage {
+    pub addr: Addr<PlayerSession>,
+}
+
+impl Message for LeaveQueueMessage {
+    type Result = ();
+}
+
+pub struct AddBotMessage {
+    pub addr: Addr<PlayerSession>,
+    pub room_id: usize,
+    pub kind: BotKind,
+}
+
+impl Message for AddBotMessage {
+    type Result = Result<(), JoinError>;
+}
+
+// Sent to both players paired by the quick-match queue
+pub struct MatchedMessage {
+    pub result: EnterRoomResult,
+}
+
+impl Message for MatchedMessage {
+    type Result = ();
 }
 
 #[derive(MessageResponse)]
 pub struct EnterRoomResult {
+    pub room_id: usize,
     pub player_index: usize,
     pub sender: Sender<GameEvent>,
+    // Sent back by the client to resume the game after losing the connection
+    pub reconnect_token: String,
+}
+
+pub struct ResumeSessionMessage {
+    pub addr: Addr<PlayerSession>,
+    pub token: String,
+}
+
+impl Message for ResumeSessionMessage {
+    type Result = Result<EnterRoomResult, JoinError>;
+}
+
+// Sent when a player session stops
+pub struct SessionClosedMessage {
+    pub addr: Addr<PlayerSession>,
+    pub room_id: usize,
+    pub player_index: usize,
+}
+
+impl Message for SessionClosedMessage {
+    type Result = ();
 }
 
 impl Message for EnterRoomMessage {
-    type Result = EnterRoomResult;
+    type Result = Result<EnterRoomResult, JoinError>;
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum JoinError {
+    InvalidDeck(DeckError),
+    // Both player slots are taken, the room can still be spectated
+    RoomFull,
+    RoomFinished,
+    RoomNotFound,
+    InvalidReconnectToken,
+    WrongPassword,
+    AlreadyQueued,
+    // The session already plays in another room
+    AlreadySeated,
+    // Seeds are only accepted for practice rooms
+    SeedNotAllowed,
+    // Practice rooms can only be filled with bots
+    PracticeRoom,
+    // Bots can only be added to practice rooms by their player
+    NotPracticeRoom,
+    // Full spectating of a running game needs a longer delay
+    DelayTooShort,
+}
+
+pub struct SpectateRoomMessage {
+    pub addr: Addr<PlayerSession>,
+    pub room_id: usize,
+    // Whether hidden information of both players is revealed
+    pub full: bool,
+    pub delay: Duration,
+    pub password: Option<String>,
+}
+
+impl Message for SpectateRoomMessage {
+    type Result = Result<(), JoinError>;
+}
+
+pub struct WatchReplayMessage {
+    pub addr: Addr<PlayerSession>,
+    pub name: String,
+    pub speed: Arc<RwLock<f64>>,
+}
+
+impl Message for WatchReplayMessage {
+    type Result = Result<(), String>;
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum RoomState {
+    // Waiting for a second player
+    Waiting,
+    InProgress,
+    Finished,
 }
 
-#[derive(Clone)]
 pub struct GameInstance {
     env: Arc<RwLock<GameEnvironment>>,
     send: Sender<GameEvent>,
+    options: RoomOptions,
+    thread: Option<JoinHandle<()>>,
+    // Since when the room has been finished or without connected players
+    idle_since: Option<Instant>,
+    // Closed rooms are removed on the next cleanup
+    closed: bool,
+}
+
+impl GameInstance {
+    pub fn state(&self) -> RoomState {
+        let env = self.env.read().unwrap();
+        if *env.game_ended.read().unwrap() {
+            RoomState::Finished
+        } else if env.active_player_count == 2 {
+            RoomState::InProgress
+        } else {
+            RoomState::Waiting
+        }
+    }
+
+    fn has_connected_players(&self) -> bool {
+        self.env.read().unwrap().session_addr.iter().flatten().any(|a| a.connected())
+    }
+
+    fn info(&self, room_id: usize) -> RoomInfo {
+        RoomInfo {
+            room_id,
+            name: self.options.name.clone(),
+            state: self.state(),
+            players: self.env.read().unwrap().active_player_count,
+            private: self.options.password.is_some(),
+            ruleset: self.options.ruleset,
+            turn_timer: self.options.turn_timer,
+            round_timer: self.options.round_timer,
+        }
+    }
 }
 
 pub struct GameServer {
     games: HashMap<usize, GameInstance>,
+    character_registry: Arc<CharacterRegistry>,
+    // Room and player index of every reconnect token
+    reconnect_tokens: HashMap<String, (usize, usize)>,
+    // Players waiting for a quick match with their decks
+    match_queue: Vec<(Addr<PlayerSession>, Deck)>,
+    next_room_id: usize,
 }
 
 impl GameServer {
-    pub fn new() -> Self {
+    pub fn new(character_registry: Arc<CharacterRegistry>) -> Self {
         GameServer {
-            games: HashMap::new()
+            games: HashMap::new(),
+            character_registry,
+            reconnect_tokens: HashMap::new(),
+            match_queue: vec![],
+            next_room_id: 1,
         }
     }
-}
 
-impl Handler<EnterRoomMessage> for GameServer {
-    type Result = EnterRoomResult;
+    fn create_room(&mut self, room_id: usize, options: RoomOptions, seed: Option<u64>) {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        println!("Room {} created with seed {}", room_id, seed);
+        let mut game_env = GameEnvironment::new(&self.character_registry, seed);
+        game_env.clocks = Clocks::new(options.clock_settings());
+        let game_env = Arc::new(RwLock::new(game_env));
+        let (send, recv) = channel();
 
-    fn handle(&mut self, msg: EnterRoomMessage, ctx: &mut Self::Context) -> Self::Result {
-        if !self.games.contains_key(&msg.room_id) {
-            let game_env = Arc::new(RwLock::new(GameEnvironment::new()));
-            let (send, recv) = channel();
-
-            // Clone the variables for the second thread
-            let game_env_clone = game_env.clone();
-            let game_ended_clone = { game_env.read().unwrap().game_ended.clone() };
-            let send_clone = send.clone();
-            thread::spawn(move || {
-                loop {
-                    let msg = recv.recv().unwrap();
-                    let mut env = game_env_clone.write().unwrap();
-
-                    if *game_ended_clone.read().unwrap() {
-                        break;
-                    }
+        // Clone the variables for the second thread
+        let game_env_clone = game_env.clone();
+        let game_ended_clone = { game_env.read().unwrap().game_ended.clone() };
+        let send_clone = send.clone();
+        let thread = thread::spawn(move || {
+            let mut last_tick = Instant::now();
+            loop {
+                let msg = match recv.recv_timeout(TICK_INTERVAL.saturating_sub(last_tick.elapsed())) {
+                    Ok(msg) => Some(msg),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let mut env = game_env_clone.write().unwrap();
+
+                // Also set when the room is closed before the game is over
+                if *game_ended_clone.read().unwrap() {
+                    break;
+                }
 
+                if let Some(msg) = msg {
                     println!("Got client message");
                     env.handle_message(&msg, &send_clone);
                 }
-            });
 
-            self.games.insert(msg.room_id, GameInstance {
-                env: game_env,
-                send,
-            });
+                // Timers are checked every tick, even while events keep arriving
+                if last_tick.elapsed() >= TICK_INTERVAL {
+                    last_tick = Instant::now();
+                    env.tick(&send_clone);
+                }
+
+                if *game_ended_clone.read().unwrap() {
+                    println!("Game ended, stopping game loop");
+                    match Replay::from_game(&env).save(room_id) {
+                        Ok(name) => println!("Replay saved as {}", name),
+                        Err(e) => println!("Failed to save replay: {}", e),
+                    }
+                    break;
+                }
+            }
+        });
+
+        self.games.insert(room_id, GameInstance {
+            env: game_env,
+            send,
+            options,
+            thread: Some(thread),
+            idle_since: None,
+            closed: false,
+        });
+    }
+
+    // Closes rooms that have been finished or left without players for too long
+    fn remove_stale_rooms(&mut self) {
+        let mut removed = vec![];
+        for (room_id, game) in self.games.iter_mut() {
+            let state = game.state();
+            let timeout = match state {
+                _ if game.closed => Duration::ZERO,
+                RoomState::Finished => FINISHED_ROOM_TIMEOUT,
+                _ if !game.has_connected_players() => ABANDONED_ROOM_TIMEOUT,
+                _ => {
+                    game.idle_since = None;
+                    continue;
+                }
+            };
+
+            let idle_since = *game.idle_since.get_or_insert_with(Instant::now);
+            if idle_since.elapsed() < timeout {
+                continue;
+            }
+
+            // Stops the game loop, it exits within a tick
+            *game.env.read().unwrap().game_ended.write().unwrap() = true;
+            if game.thread.as_ref().is_none_or(|a| a.is_finished()) {
+                removed.push(*room_id);
+            }
         }
-        let game_arc = self.games.get(&msg.room_id).unwrap();
 
-        let mut game_env = game_arc.env.write().unwrap();
-        let player_index = game_env.add_player(msg.addr);
+        for room_id in removed {
+            if let Some(mut game) = self.games.remove(&room_id) {
+                if let Some(thread) = game.thread.take() {
+                    thread.join().ok();
+                }
+            }
+
+            self.reconnect_tokens.retain(|_, (room, _)| *room != room_id);
+            println!("Room {} removed", room_id);
+        }
+    }
+
+    // Stops the game loop right away, the room counts as finished until the next cleanup joins the thread
+    fn close_room(&mut self, room_id: usize) {
+        if let Some(game) = self.games.get_mut(&room_id) {
+            *game.env.read().unwrap().game_ended.write().unwrap() = true;
+            game.closed = true;
+            println!("Room {} closed", room_id);
+        }
+    }
+
+    // Whether the session plays in a room that is not over yet
+    fn is_seated(&self, addr: &Addr<PlayerSession>) -> bool {
+        self.games.values()
+            .filter(|a| a.state() != RoomState::Finished)
+            .any(|a| a.env.read().unwrap().session_addr.iter().flatten().any(|b| b == addr))
+    }
+
+    fn leave_queue(&mut self, addr: &Addr<PlayerSession>) {
+        self.match_queue.retain(|(a, _)| a != addr);
+    }
+
+    // Room ids picked by clients are kept, created rooms take the next free id
+    fn next_room_id(&mut self) -> usize {
+        while self.games.contains_key(&self.next_room_id) {
+            self.next_room_id += 1;
+        }
+        self.next_room_id
+    }
+
+    fn add_player(&mut self, room_id: usize, addr: Addr<PlayerSession>, deck: &Deck) -> EnterRoomResult {
+        let game = self.games.get(&room_id).unwrap();
+        let sender = game.send.clone();
+        let player_index = game.env.write().unwrap().add_player(Some(addr), Player::new(&self.character_registry, deck));
 
         EnterRoomResult {
+            room_id,
+            player_index,
+            sender,
+            reconnect_token: self.create_reconnect_token(room_id, player_index),
+        }
+    }
+
+    fn create_reconnect_token(&mut self, room_id: usize, player_index: usize) -> String {
+        let token = format!("{:016x}{:016x}", thread_rng().gen::<u64>(), thread_rng().gen::<u64>());
+        self.reconnect_tokens.insert(token.clone(), (room_id, player_index));
+        token
+    }
+}
+
+impl Handler<EnterRoomMessage> for GameServer {
+    type Result = Result<EnterRoomResult, JoinError>;
+
+    fn handle(&mut self, msg: EnterRoomMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let ruleset = match self.games.get(&msg.room_id) {
+            Some(game) => {
+                match game.state() {
+                    RoomState::InProgress => return Err(JoinError::RoomFull),
+                    RoomState::Finished => return Err(JoinError::RoomFinished),
+                    RoomState::Waiting => {}
+                }
+
+                if game.options.practice {
+                    return Err(JoinError::PracticeRoom);
+                }
+                if !game.options.check_password(msg.password.as_deref()) {
+                    return Err(JoinError::WrongPassword);
+                }
+                game.options.ruleset
+            }
+            None => Ruleset::Standard,
+        };
+        msg.deck.validate(&self.character_registry, ruleset).map_err(JoinError::InvalidDeck)?;
+
+        if !self.games.contains_key(&msg.room_id) {
+            self.create_room(msg.room_id, RoomOptions::default(), None);
+        }
+
+        self.leave_queue(&msg.addr);
+        Ok(self.add_player(msg.room_id, msg.addr, &msg.deck))
+    }
+}
+
+impl Handler<CreateRoomMessage> for GameServer {
+    type Result = Result<EnterRoomResult, JoinError>;
+
+    fn handle(&mut self, msg: CreateRoomMessage, _ctx: &mut Self::Context) -> Self::Result {
+        if msg.seed.is_some() && !msg.options.practice {
+            return Err(JoinError::SeedNotAllowed);
+        }
+        msg.deck.validate(&self.character_registry, msg.options.ruleset).map_err(JoinError::InvalidDeck)?;
+
+        let room_id = self.next_room_id();
+        self.create_room(room_id, msg.options, msg.seed);
+        self.leave_queue(&msg.addr);
+        Ok(self.add_player(room_id, msg.addr, &msg.deck))
+    }
+}
+
+impl Handler<ListRoomsMessage> for GameServer {
+    type Result = Vec<RoomInfo>;
+
+    fn handle(&mut self, _msg: ListRoomsMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let mut rooms: Vec<RoomInfo> = self.games.iter()
+            .filter(|(_, game)| !game.options.practice)
+            .map(|(room_id, game)| game.info(*room_id))
+            .filter(|a| a.state == RoomState::Waiting)
+            .collect();
+        rooms.sort_by_key(|a| a.room_id);
+        rooms
+    }
+}
+
+impl Handler<QuickMatchMessage> for GameServer {
+    type Result = Result<(), JoinError>;
+
+    fn handle(&mut self, msg: QuickMatchMessage, _ctx: &mut Self::Context) -> Self::Result {
+        msg.deck.validate(&self.character_registry, Ruleset::Standard).map_err(JoinError::InvalidDeck)?;
+
+        // Players who left or got into a room since they queued are skipped
+        let queue = std::mem::take(&mut self.match_queue);
+        self.match_queue = queue.into_iter().filter(|(addr, _)| addr.connected() && !self.is_seated(addr)).collect();
+        if self.match_queue.iter().any(|(addr, _)| *addr == msg.addr) {
+            return Err(JoinError::AlreadyQueued);
+        }
+        if self.is_seated(&msg.addr) {
+            return Err(JoinError::AlreadySeated);
+        }
+
+        // Players are paired in the order they queued
+        let Some((opponent, opponent_deck)) = (!self.match_queue.is_empty()).then(|| self.match_queue.remove(0)) else {
+            self.match_queue.push((msg.addr, msg.deck));
+            return Ok(());
+        };
+
+        let room_id = self.next_room_id();
+        let options = RoomOptions {
+            name: "Quick match".to_string(),
+            ..RoomOptions::default()
+        };
+        self.create_room(room_id, options, None);
+        println!("Quick match found, room {}", room_id);
+
+        let result = self.add_player(room_id, opponent.clone(), &opponent_deck);
+        opponent.do_send(MatchedMessage { result });
+        let result = self.add_player(room_id, msg.addr.clone(), &msg.deck);
+        msg.addr.do_send(MatchedMessage { result });
+        Ok(())
+    }
+}
+
+impl Handler<AddBotMessage> for GameServer {
+    type Result = Result<(), JoinError>;
+
+    fn handle(&mut self, msg: AddBotMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let game = self.games.get(&msg.room_id).ok_or(JoinError::RoomNotFound)?;
+        match game.state() {
+            RoomState::InProgress => return Err(JoinError::RoomFull),
+            RoomState::Finished => return Err(JoinError::RoomFinished),
+            RoomState::Waiting => {}
+        }
+
+        let seated = game.env.read().unwrap().session_addr.iter().flatten().any(|a| *a == msg.addr);
+        if !game.options.practice || !seated {
+            return Err(JoinError::NotPracticeRoom);
+        }
+
+        // Bots play the default deck and start the game like a client setting up
+        let player = Player::new(&self.character_registry, &Deck::default());
+        let index = game.env.write().unwrap().add_bot(create_bot(msg.kind), player);
+        game.send.send(GameEvent::SetupClient(index)).ok();
+        Ok(())
+    }
+}
+
+impl Handler<LeaveQueueMessage> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveQueueMessage, _ctx: &mut Self::Context) -> Self::Result {
+        self.leave_queue(&msg.addr);
+    }
+}
+
+impl Handler<ResumeSessionMessage> for GameServer {
+    type Result = Result<EnterRoomResult, JoinError>;
+
+    fn handle(&mut self, msg: ResumeSessionMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let (room_id, player_index) = *self.reconnect_tokens.get(&msg.token).ok_or(JoinError::InvalidReconnectToken)?;
+        let game = self.games.get(&room_id).ok_or(JoinError::RoomNotFound)?;
+        let sender = game.send.clone();
+
+        {
+            let mut env = game.env.write().unwrap();
+            if *env.game_ended.read().unwrap() {
+                return Err(JoinError::RoomNotFound);
+            }
+
+            // The previous session may still be open, it must not act for the player anymore
+            if let Some(old) = env.session_addr[player_index].replace(msg.addr.clone()).filter(|a| *a != msg.addr) {
+                old.do_send(SessionReplacedMessage { room_id, player_index });
+            }
+        }
+
+        // Every token can only be used once
+        self.reconnect_tokens.remove(&msg.token);
+        Ok(EnterRoomResult {
+            room_id,
             player_index,
-            sender: game_arc.send.clone(),
+            sender,
+            reconnect_token: self.create_reconnect_token(room_id, player_index),
+        })
+    }
+}
+
+impl Handler<SessionClosedMessage> for GameServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionClosedMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let Some(game) = self.games.get(&msg.room_id) else {
+            return;
+        };
+
+        // The player may already have resumed the game from another session
+        if game.env.read().unwrap().session_addr[msg.player_index].as_ref() != Some(&msg.addr) {
+            return;
+        }
+
+        // Nobody is left to play a room that has not started, it is closed right away
+        if game.state() == RoomState::Waiting {
+            self.close_room(msg.room_id);
+        } else {
+            game.send.send(GameEvent::Disconnected(msg.player_index)).ok();
         }
     }
 }
 
+impl Handler<SpectateRoomMessage> for GameServer {
+    type Result = Result<(), JoinError>;
+
+    fn handle(&mut self, msg: SpectateRoomMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let game = self.games.get(&msg.room_id).ok_or(JoinError::RoomNotFound)?;
+        if !game.options.check_password(msg.password.as_deref()) {
+            return Err(JoinError::WrongPassword);
+        }
+        if msg.full && msg.delay < MIN_FULL_SPECTATOR_DELAY && game.state() != RoomState::Finished {
+            return Err(JoinError::DelayTooShort);
+        }
+        game.env.write().unwrap().add_spectator(msg.addr.clone(), msg.full);
+        self.leave_queue(&msg.addr);
+        Ok(())
+    }
+}
+
+impl Handler<WatchReplayMessage> for GameServer {
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: WatchReplayMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let replay = Replay::load(&msg.name)?;
+        replay.play(self.character_registry.clone(), msg.addr, msg.speed);
+        Ok(())
+    }
+}
+
 impl Actor for GameServer {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(CLEANUP_INTERVAL, |act, _ctx| act.remove_stale_rooms());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use crate::cards::{SummonedCard, SupportCard};
+use crate::damage::{CharacterRef, DamageResult, DamageSource};
+use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::reactions::Reaction;
+use crate::status::{Status, StatusOwner};
+
+// Events dispatched to every status, summon and support on the field
+#[derive(Clone, Debug)]
+pub enum HookEvent {
+    BeforeSkill(CharacterRef, SkillType),
+    AfterSkill(CharacterRef, SkillType),
+    // Seen as damage dealt by the source's side and damage taken by the target's side
+    Damage(DamageResult),
+    // Reaction, source and target of the damage that triggered it
+    Reaction(Reaction, DamageSource, CharacterRef),
+    // Player index, previous active character, new active character
+    CharacterSwitched(usize, usize, usize),
+    // Player index, card name
+    CardPlayed(usize, &'static str),
+    // Player index, whether the dice were rerolled
+    DiceRolled(usize, bool),
+    CharacterDefeated(CharacterRef),
+    // Round number
+    RoundStart(usize),
+    RoundEnd(usize),
+}
+
+impl HookEvent {
+    // Player whose entities are notified first, the other player follows
+    pub fn player(&self) -> Option<usize> {
+        match self {
+            HookEvent::BeforeSkill(character, _) | HookEvent::AfterSkill(character, _) => Some(character.player),
+            HookEvent::Damage(result) => Some(result.source.player),
+            HookEvent::Reaction(_, source, _) => Some(source.player),
+            HookEvent::CharacterSwitched(id, _, _) | HookEvent::CardPlayed(id, _)
+            | HookEvent::DiceRolled(id, _) => Some(*id),
+            HookEvent::CharacterDefeated(character) => Some(character.player),
+            HookEvent::RoundStart(_) | HookEvent::RoundEnd(_) => None,
+        }
+    }
+}
+
+enum Subscriber {
+    Status(StatusOwner, Arc<dyn Status>),
+    Summon(usize, Arc<dyn SummonedCard>),
+    Support(usize, Arc<dyn SupportCard>),
+}
+
+impl GameEnvironment {
+    // Notifies every entity of the event
+    // Order: the acting player (or the first player of the round) before the other player, and for each player
+    // active character statuses, other character statuses, combat statuses, summons, then supports
+    pub fn dispatch(&mut self, event: HookEvent) {
+        let first = event.player().unwrap_or(self.first_player);
+        let mut subscribers = self.subscribers(first);
+        subscribers.extend(self.subscribers(1 - first));
+
+        for subscriber in subscribers {
+            match subscriber {
+                Subscriber::Status(owner, status) => status.on_event(owner, &event, self),
+                Subscriber::Summon(owner, summon) => summon.on_event(owner, &event, self),
+                Subscriber::Support(owner, support) => support.on_event(owner, &event, self),
+            }
+        }
+
+        for player in self.players.iter_mut() {
+            player.remove_expired_statuses();
+        }
+    }
+
+    fn subscribers(&self, index: usize) -> Vec<Subscriber> {
+        let player = &self.players[index];
+        let mut subscribers = vec![];
+
+        let mut characters: Vec<usize> = (0..player.characters.len()).collect();
+        characters.retain(|a| *a != player.active_character);
+        characters.insert(0, player.active_character);
+
+        for character in characters {
+            for status in player.characters[character].statuses.iter() {
+                let owner = StatusOwner { player: index, character: Some(character) };
+                subscribers.push(Subscriber::Status(owner, status.clone()));
+            }
+        }
+
+        for status in player.combat_statuses.iter() {
+            let owner = StatusOwner { player: index, character: None };
+            subscribers.push(Subscriber::Status(owner, status.clone()));
+        }
+
+        for summon in player.summoned_area[0..player.summoned_area_count].iter() {
+            subscribers.push(Subscriber::Summon(index, summon.clone()));
+        }
+
+        for support in player.support_area[0..player.support_area_count].iter() {
+            subscribers.push(Subscriber::Support(index, support.clone()));
+        }
+
+        subscribers
+    }
+}
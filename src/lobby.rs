@@ -0,0 +1,61 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::clock::ClockSettings;
+use crate::game_server::RoomState;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub enum Ruleset {
+    #[default]
+    Standard,
+    // Decks of any size with any number of copies
+    Casual,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RoomOptions {
+    #[serde(default)]
+    pub name: String,
+    // Private rooms can only be joined with the password
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub ruleset: Ruleset,
+    // Seconds a player has for each action, no limit when None
+    #[serde(default)]
+    pub turn_timer: Option<u64>,
+    // Seconds a player has for all actions of a round
+    #[serde(default)]
+    pub round_timer: Option<u64>,
+    // Practice rooms are only filled by bots, they are not listed and can be seeded
+    #[serde(default)]
+    pub practice: bool,
+}
+
+impl RoomOptions {
+    pub fn clock_settings(&self) -> ClockSettings {
+        ClockSettings {
+            action: self.turn_timer.map(Duration::from_secs),
+            round: self.round_timer.map(Duration::from_secs),
+        }
+    }
+
+    pub fn check_password(&self, password: Option<&str>) -> bool {
+        match self.password.as_deref() {
+            Some(expected) => password == Some(expected),
+            None => true,
+        }
+    }
+}
+
+// Entry of the room list, the password is never sent
+#[derive(Serialize, Clone, Debug)]
+pub struct RoomInfo {
+    pub room_id: usize,
+    pub name: String,
+    pub state: RoomState,
+    pub players: usize,
+    pub private: bool,
+    pub ruleset: Ruleset,
+    pub turn_timer: Option<u64>,
+    pub round_timer: Option<u64>,
+}
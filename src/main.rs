@@ -1,7 +1,9 @@
 use actix::{Actor, Addr};
 use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws;
-use crate::game_server::GameServer;
+use std::sync::Arc;
+use crate::characters::registry::{CharacterRegistry, CHARACTER_DATA_DIR};
+use crate::game_server::{GameServer, ListRoomsMessage};
 use crate::player_session::PlayerSession;
 
 mod dice_set;
@@ -11,10 +13,26 @@ mod game_environment;
 mod player;
 mod game_events;
 mod server_messages;
+mod client_messages;
 mod characters;
+mod action_cards;
 mod operation_context;
 mod game_server;
 mod player_session;
+mod reactions;
+mod damage;
+mod phase;
+mod validation;
+mod cost;
+mod deck;
+mod status;
+mod hooks;
+mod snapshot;
+mod state_sync;
+mod replay;
+mod lobby;
+mod clock;
+mod bot;
 
 async fn index(req: HttpRequest, stream: web::Payload, srv: web::Data<Addr<GameServer>>) -> Result<HttpResponse, Error> {
     let resp = ws::start(
@@ -23,13 +41,23 @@ async fn index(req: HttpRequest, stream: web::Payload, srv: web::Data<Addr<GameS
     resp
 }
 
+// Rooms waiting for a second player
+async fn list_rooms(srv: web::Data<Addr<GameServer>>) -> Result<HttpResponse, Error> {
+    let rooms = srv.send(ListRoomsMessage).await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(rooms))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let server = GameServer::new().start();
+    let character_registry = CharacterRegistry::load(CHARACTER_DATA_DIR)
+        .expect("Failed to load character definitions");
+    let server = GameServer::new(Arc::new(character_registry)).start();
 
     HttpServer::new(move || App::new()
         .app_data(web::Data::new(server.clone()))
-        .route("/", web::get().to(index)))
+        .route("/", web::get().to(index))
+        .route("/rooms", web::get().to(list_rooms)))
         .bind(("127.0.0.1", 9001))?
         .workers(2)
         .run()
@@ -1,3 +1,5 @@
+use crate::damage::{CharacterRef, DamageSource};
+
 pub struct OperationContext {
     pub subject_player: usize,
     pub subject_character: usize,
@@ -14,4 +16,18 @@ impl OperationContext {
             target_character,
         }
     }
-}
\ No newline at end of file
+
+    pub fn source(&self) -> DamageSource {
+        DamageSource {
+            player: self.subject_player,
+            character: Some(self.subject_character),
+        }
+    }
+
+    pub fn target(&self) -> CharacterRef {
+        CharacterRef {
+            player: self.target_player,
+            character: self.target_character,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum Phase {
+    // Waiting for both players to join the room
+    Waiting,
+    // Players may swap cards of their opening hand
+    Mulligan,
+    // Dice are being rolled at the start of a round
+    Roll,
+    // Players may reroll their dice before the action phase
+    Reroll,
+    // Players take turns performing actions
+    Action,
+    // End phase effects resolve and cards are drawn
+    End,
+    GameOver,
+}
@@ -1,58 +1,100 @@
-use std::mem::{MaybeUninit, transmute};
 use std::sync::Arc;
-use crate::cards::{EmptyCard, SummonedCard, SupportCard};
+use rand::prelude::*;
+use crate::card_set::CardSet;
+use crate::cards::{ActionCard, EmptyCard, SummonedCard, SupportCard};
 use crate::characters::character::Character;
-use crate::characters::fischl::fischl;
-use crate::characters::ganyu::ganyu;
-use crate::characters::yoimiya::yoimiya;
+use crate::characters::registry::CharacterRegistry;
+use crate::cost::{AppliedModifier, Cost, CostModifier, CostSubject};
+use crate::deck::Deck;
 use crate::dice_set::{DiceSet, ElementType};
+use crate::status::{add_status, Status, StatusOwner};
+
+pub const OPENING_HAND_SIZE: usize = 5;
+pub const MAX_HAND_SIZE: usize = 10;
 
 pub struct Player {
     pub dice_set: DiceSet,
     pub support_area: [Arc<dyn SupportCard>; 4],
     pub summoned_area: [Arc<dyn SummonedCard>; 4],
     pub characters: [Character; 3],
+    pub deck: Deck,
+    pub card_set: CardSet,
+    pub draw_pile: Vec<Arc<dyn ActionCard>>,
+    pub hand: Vec<Arc<dyn ActionCard>>,
     pub active_character: usize,
     pub support_area_count: usize,
     pub summoned_area_count: usize,
     pub reroll_chances: usize,
-}
-
-macro_rules! init_array {
-    ($elem:ty, $len:expr, $def:expr) => {
- // macro expand to this code
-        unsafe {
-            let mut arr: [MaybeUninit<$elem>; $len] = MaybeUninit::uninit().assume_init();
-            for elem in &mut arr {
-                elem.write($def);
-            }
-            transmute::<_, [$elem; $len]>(arr)
-        }
-    }
+    pub combat_statuses: Vec<Arc<dyn Status>>,
+    pub cost_modifiers: Vec<Arc<dyn CostModifier>>,
+    // Whether the player has to choose a new active character
+    pub choosing_active: bool,
 }
 
 impl Player {
-    pub fn new() -> Self {
+    // The deck has to be validated beforehand
+    pub fn new(registry: &CharacterRegistry, deck: &Deck) -> Self {
+        let characters: Vec<Character> = deck.characters.iter().map(|a| registry.create(a).unwrap()).collect();
+
         Player {
             dice_set: DiceSet::default(),
-            support_area: init_array!(Arc<dyn SupportCard>, 4, Arc::new(EmptyCard {})),
-            summoned_area: init_array!(Arc<dyn SummonedCard>, 4, Arc::new(EmptyCard {})),
-            characters: [
-                yoimiya(),
-                fischl(),
-                ganyu()
-            ],
+            support_area: std::array::from_fn(|_| Arc::new(EmptyCard {}) as Arc<dyn SupportCard>),
+            summoned_area: std::array::from_fn(|_| Arc::new(EmptyCard {}) as Arc<dyn SummonedCard>),
+            characters: characters.try_into().unwrap_or_else(|_| panic!("A deck needs three characters")),
+            deck: deck.clone(),
+            card_set: CardSet::from_names(&deck.cards).unwrap(),
+            draw_pile: vec![],
+            hand: vec![],
             active_character: 0usize,
             support_area_count: 0usize,
             summoned_area_count: 0usize,
             reroll_chances: 0usize,
+            combat_statuses: vec![],
+            cost_modifiers: vec![],
+            choosing_active: false,
         }
     }
 }
 
 impl Player {
+    // Elements of the characters, the active character's first
     pub fn get_character_elements(&self) -> Vec<ElementType> {
-        self.characters.iter().map(|a| a.element).collect()
+        let mut elements = vec![self.characters[self.active_character].element];
+        elements.extend(self.characters.iter().map(|a| a.element));
+        elements
+    }
+
+    pub fn shuffle_draw_pile(&mut self, rng: &mut StdRng) {
+        self.draw_pile = self.card_set.cards.clone();
+        self.draw_pile.shuffle(rng);
+    }
+
+    // Cards drawn while the hand is full are discarded
+    pub fn draw_cards(&mut self, num: usize) {
+        for _ in 0..num {
+            let card = match self.draw_pile.pop() {
+                Some(card) => card,
+                None => break,
+            };
+
+            if self.hand.len() < MAX_HAND_SIZE {
+                self.hand.push(card);
+            } else {
+                println!("Hand is full, {} is discarded", card.name());
+            }
+        }
+    }
+
+    // Puts the cards back into the draw pile and draws the same number of cards
+    pub fn swap_cards(&mut self, indices: &[usize], rng: &mut StdRng) {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+
+        let returned: Vec<Arc<dyn ActionCard>> = indices.iter().map(|i| self.hand.remove(*i)).collect();
+        self.draw_cards(returned.len());
+
+        self.draw_pile.extend(returned);
+        self.draw_pile.shuffle(rng);
     }
 
     pub fn insert_support(&mut self, card: Arc<dyn SupportCard>) {
@@ -61,7 +103,7 @@ impl Player {
     }
 
     pub fn remove_support(&mut self, index: usize) {
-        for i in index..self.support_area_count {
+        for i in index..self.support_area_count - 1 {
             self.support_area[i] = self.support_area[i + 1].clone();
         }
 
@@ -71,11 +113,85 @@ impl Player {
 
     pub fn insert_summoned(&mut self, card: Arc<dyn SummonedCard>) {
         self.summoned_area[self.summoned_area_count] = card;
-        self.support_area_count += 1;
+        self.summoned_area_count += 1;
+    }
+
+    pub fn find_summoned(&self, name: &str) -> Option<Arc<dyn SummonedCard>> {
+        self.summoned_area[0..self.summoned_area_count].iter().find(|a| a.name() == name).cloned()
+    }
+
+    pub fn add_combat_status(&mut self, status: Arc<dyn Status>) {
+        add_status(&mut self.combat_statuses, status);
+    }
+
+    pub fn remove_expired_statuses(&mut self) {
+        self.combat_statuses.retain(|a| !a.expired());
+        for character in self.characters.iter_mut() {
+            character.statuses.retain(|a| !a.expired());
+        }
+    }
+
+    // Every status of the player along with its owner, combat statuses first
+    pub fn all_statuses(&self, index: usize) -> Vec<(StatusOwner, Arc<dyn Status>)> {
+        let mut statuses: Vec<(StatusOwner, Arc<dyn Status>)> = self.combat_statuses.iter()
+            .map(|a| (StatusOwner { player: index, character: None }, a.clone()))
+            .collect();
+
+        for (i, character) in self.characters.iter().enumerate() {
+            for status in character.statuses.iter() {
+                statuses.push((StatusOwner { player: index, character: Some(i) }, status.clone()));
+            }
+        }
+
+        statuses
+    }
+
+    // Cost after reductions from modifiers and statuses, along with the reductions that were applied
+    pub fn effective_cost(&self, index: usize, subject: CostSubject, base: &Cost) -> (Cost, Vec<AppliedModifier>) {
+        let mut cost = base.clone();
+        let mut applied: Vec<AppliedModifier> = self.cost_modifiers.iter()
+            .filter(|a| a.remaining_uses() > 0 && a.modify(subject, &mut cost))
+            .map(|a| AppliedModifier::Modifier(a.clone()))
+            .collect();
+
+        for (owner, status) in self.all_statuses(index) {
+            if !status.expired() && status.modify_cost(owner, subject, &mut cost) {
+                applied.push(AppliedModifier::Status(status));
+            }
+        }
+
+        (cost, applied)
+    }
+
+    pub fn consume_cost_modifiers(&mut self, applied: Vec<AppliedModifier>) {
+        for modifier in applied {
+            match modifier {
+                AppliedModifier::Modifier(modifier) => modifier.consume(),
+                AppliedModifier::Status(status) => status.counter().consume(),
+            }
+        }
+
+        self.cost_modifiers.retain(|a| a.remaining_uses() > 0);
+        self.remove_expired_statuses();
+    }
+
+    pub fn all_defeated(&self) -> bool {
+        self.characters.iter().all(|a| a.defeated)
+    }
+
+    pub fn alive_characters(&self) -> Vec<usize> {
+        (0..self.characters.len()).filter(|i| !self.characters[*i].defeated).collect()
+    }
+
+    // Next character after the given one that is still alive
+    pub fn next_alive_character(&self, from: usize) -> Option<usize> {
+        (1..self.characters.len())
+            .map(|offset| (from + offset) % self.characters.len())
+            .find(|i| !self.characters[*i].defeated)
     }
 
     pub fn remove_summoned(&mut self, index: usize) {
-        for i in index..self.summoned_area_count {
+        for i in index..self.summoned_area_count - 1 {
             self.summoned_area[i] = self.summoned_area[i + 1].clone();
         }
 
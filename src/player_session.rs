@@ -1,47 +1,348 @@
+use std::sync::{Arc, RwLock};
 use std::sync::mpsc::Sender;
-use actix::{Actor, ActorContext, ActorFutureExt, ActorTryFutureExt, Addr, AsyncContext, ContextFutureSpawner, fut, Handler, StreamHandler, WrapFuture};
+use std::time::Duration;
+use actix::{Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, ContextFutureSpawner, fut, Handler, StreamHandler, WrapFuture};
 use actix_web_actors::ws;
 use serde::Serialize;
 use serde_json::{json, Value};
+use crate::bot::BotKind;
+use crate::client_messages::{ClientMessage, PROTOCOL_VERSION, ProtocolError};
+use crate::deck::Deck;
 use crate::game_events::GameEvent;
-use crate::game_events::GameEvent::SetupClient;
-use crate::game_server::{EnterRoomMessage, GameServer};
+use crate::game_events::GameEvent::{ChangeActive, ChooseActive, Concede, DeclareRoundEnd, Reconnected, RerollDice, SetupClient, SwapCards, Tune, UseActionCard, UseSkill};
+use crate::game_server::{AddBotMessage, CreateRoomMessage, EnterRoomMessage, EnterRoomResult, GameServer, JoinError, LeaveQueueMessage, ListRoomsMessage, MatchedMessage, QuickMatchMessage, ResumeSessionMessage, SessionClosedMessage, SpectateRoomMessage, WatchReplayMessage};
+use crate::lobby::RoomOptions;
 use crate::server_messages::*;
+use crate::state_sync::StateSync;
 
 #[derive(Debug)]
 pub struct PlayerSession {
     server: Addr<GameServer>,
     game_loop_channel: Option<Sender<GameEvent>>,
+    room_id: Option<usize>,
     player_index: usize,
+    state_sync: StateSync,
+    handshake_done: bool,
+    replay_speed: Arc<RwLock<f64>>,
+    // Set for spectators, state updates are sent after this delay
+    spectator_delay: Option<Duration>,
 }
 
 impl PlayerSession {
     pub fn new(server_addr: Addr<GameServer>) -> Self {
-        let ac = PlayerSession {
+        PlayerSession {
             server: server_addr,
             game_loop_channel: None,
+            room_id: None,
             player_index: 0,
+            state_sync: StateSync::new(),
+            handshake_done: false,
+            replay_speed: Arc::new(RwLock::new(1.0)),
+            spectator_delay: None,
+        }
+    }
+
+    fn join_room(&mut self, room_id: usize, deck: Deck, password: Option<String>, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(EnterRoomMessage {
+            addr: ctx.address(),
+            room_id,
+            deck,
+            password,
+        }).into_actor(self).then(|res, act, ctx| {
+            act.enter_room(res.unwrap(), ctx);
+            fut::ready(())
+        }).wait(ctx);
+    }
+
+    fn create_room(&mut self, options: RoomOptions, deck: Deck, seed: Option<u64>, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(CreateRoomMessage {
+            addr: ctx.address(),
+            options,
+            deck,
+            seed,
+        }).into_actor(self).then(|res, act, ctx| {
+            act.enter_room(res.unwrap(), ctx);
+            fut::ready(())
+        }).wait(ctx);
+    }
+
+    fn enter_room(&mut self, result: Result<EnterRoomResult, JoinError>, ctx: &mut <PlayerSession as Actor>::Context) {
+        match result {
+            Ok(result) => {
+                println!("Player {} request character list", result.player_index);
+                let event = SetupClient(result.player_index);
+                self.bind_room(result, event, ctx);
+            }
+            Err(reason) => ctx.address().do_send(JoinRejectedMessage { reason }),
+        }
+    }
+
+    fn list_rooms(&mut self, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(ListRoomsMessage).into_actor(self).then(|res, _act, ctx| {
+            if let Ok(rooms) = res {
+                let json = PlayerSession::message_to_json("RoomList", RoomListMessage { rooms });
+                println!("Server: {}", json);
+                ctx.text(json.to_string());
+            }
+            fut::ready(())
+        }).wait(ctx);
+    }
+
+    fn quick_match(&mut self, deck: Deck, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(QuickMatchMessage {
+            addr: ctx.address(),
+            deck,
+        }).into_actor(self).then(|res, _act, ctx| {
+            if let Err(reason) = res.unwrap() {
+                ctx.address().do_send(JoinRejectedMessage { reason });
+            }
+            fut::ready(())
+        }).wait(ctx);
+    }
+
+    fn add_bot(&mut self, kind: BotKind, ctx: &mut <PlayerSession as Actor>::Context) {
+        let Some(room_id) = self.room_id else {
+            PlayerSession::send_error(ProtocolError::NotInRoom, "Join a room first".to_string(), ctx);
+            return;
         };
 
-        return ac;
+        self.server.send(AddBotMessage {
+            addr: ctx.address(),
+            room_id,
+            kind,
+        }).into_actor(self).then(|res, _act, ctx| {
+            if let Err(reason) = res.unwrap() {
+                ctx.address().do_send(JoinRejectedMessage { reason });
+            }
+            fut::ready(())
+        }).wait(ctx);
     }
 
-    fn join_room(&mut self, room_id: usize, ctx: &mut <PlayerSession as Actor>::Context) {
-        self.server.send(EnterRoomMessage {
+    fn resume(&mut self, token: String, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(ResumeSessionMessage {
+            addr: ctx.address(),
+            token,
+        }).into_actor(self).then(|res, act, ctx| {
+            match res.unwrap() {
+                Ok(result) => {
+                    println!("Player {} resumed the game", result.player_index);
+                    let event = Reconnected(result.player_index);
+                    act.bind_room(result, event, ctx);
+                }
+                Err(reason) => ctx.address().do_send(JoinRejectedMessage { reason }),
+            }
+            fut::ready(())
+        }).wait(ctx);
+    }
+
+    // Binds the session to a player of the room, the token lets the client resume it later
+    fn bind_room(&mut self, result: EnterRoomResult, event: GameEvent, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.game_loop_channel = Some(result.sender);
+        self.room_id = Some(result.room_id);
+        self.player_index = result.player_index;
+
+        let json = PlayerSession::message_to_json("JoinedRoom", JoinedRoomMessage {
+            room_id: result.room_id,
+            player_index: result.player_index,
+            reconnect_token: result.reconnect_token,
+        });
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+
+        self.send_event(event);
+    }
+
+    fn spectate(&mut self, room_id: usize, full: bool, delay: Duration, password: Option<String>, ctx: &mut <PlayerSession as Actor>::Context) {
+        // Set before joining so the first state is delayed as well
+        self.spectator_delay = Some(delay);
+        self.server.send(SpectateRoomMessage {
             addr: ctx.address(),
             room_id,
+            full,
+            delay,
+            password,
         }).into_actor(self).then(|res, act, ctx| {
-            let result = res.unwrap();
-            act.game_loop_channel = Some(result.sender);
-            act.player_index = result.player_index;
+            if let Err(reason) = res.unwrap() {
+                act.spectator_delay = None;
+                ctx.address().do_send(JoinRejectedMessage { reason });
+            }
+            fut::ready(())
+        }).wait(ctx);
+    }
 
-            println!("Player {} request character list", act.player_index);
-            act.game_loop_channel.as_ref().unwrap().send(SetupClient(act.player_index))
-                .expect("TODO: panic message");
+    fn watch_replay(&mut self, name: String, ctx: &mut <PlayerSession as Actor>::Context) {
+        self.server.send(WatchReplayMessage {
+            addr: ctx.address(),
+            name,
+            speed: self.replay_speed.clone(),
+        }).into_actor(self).then(|res, _act, ctx| {
+            if let Ok(Err(e)) = res {
+                PlayerSession::send_error(ProtocolError::ReplayUnavailable, e, ctx);
+            }
             fut::ready(())
         }).wait(ctx);
     }
 
+    fn send_frame(frame: Value, ctx: &mut <PlayerSession as Actor>::Context) {
+        println!("Server: {}", frame);
+        ctx.text(frame.to_string());
+    }
+
+    fn resync(&mut self, ctx: &mut <PlayerSession as Actor>::Context) {
+        if let Some(frame) = self.state_sync.resync_frame() {
+            PlayerSession::send_frame(frame, ctx);
+        }
+    }
+
+    fn send_error(error: ProtocolError, detail: String, ctx: &mut <PlayerSession as Actor>::Context) {
+        let json = PlayerSession::message_to_json("Error", ErrorMessage { error, detail });
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+
+    fn handle_client_message(&mut self, msg: ClientMessage, ctx: &mut <PlayerSession as Actor>::Context) {
+        let msg = match msg {
+            ClientMessage::Hello { version } => {
+                if version != PROTOCOL_VERSION {
+                    let detail = format!("Server speaks protocol version {}", PROTOCOL_VERSION);
+                    PlayerSession::send_error(ProtocolError::UnsupportedVersion, detail, ctx);
+                    return;
+                }
+
+                self.handshake_done = true;
+                let json = PlayerSession::message_to_json("Welcome", WelcomeMessage {
+                    protocol_version: PROTOCOL_VERSION,
+                });
+                println!("Server: {}", json);
+                ctx.text(json.to_string());
+                return;
+            }
+
+            ClientMessage::Unknown => {
+                PlayerSession::send_error(ProtocolError::UnknownMessage, "Unknown message type".to_string(), ctx);
+                return;
+            }
+
+            _ if !self.handshake_done => {
+                PlayerSession::send_error(ProtocolError::HandshakeRequired, "Send Hello first".to_string(), ctx);
+                return;
+            }
+
+            ClientMessage::JoinRoom { .. } | ClientMessage::CreateRoom { .. } | ClientMessage::QuickMatch { .. }
+            | ClientMessage::Spectate { .. } | ClientMessage::Resume { .. }
+                if self.room_id.is_some() || self.spectator_delay.is_some() => {
+                PlayerSession::send_error(ProtocolError::AlreadyInRoom, "The session is already in a room".to_string(), ctx);
+                return;
+            }
+
+            ClientMessage::Spectate { room, full, delay, password } => {
+                self.spectate(room, full, Duration::from_secs(delay), password, ctx);
+                return;
+            }
+
+            ClientMessage::Resume { token } => {
+                self.resume(token, ctx);
+                return;
+            }
+
+            ClientMessage::JoinRoom { room, deck, password } => {
+                self.join_room(room, deck.unwrap_or_default(), password, ctx);
+                return;
+            }
+
+            ClientMessage::CreateRoom { options, deck, seed } => {
+                self.create_room(options, deck.unwrap_or_default(), seed, ctx);
+                return;
+            }
+
+            ClientMessage::ListRooms => {
+                self.list_rooms(ctx);
+                return;
+            }
+
+            ClientMessage::QuickMatch { deck } => {
+                self.quick_match(deck.unwrap_or_default(), ctx);
+                return;
+            }
+
+            ClientMessage::AddBot { kind } => {
+                self.add_bot(kind, ctx);
+                return;
+            }
+
+            ClientMessage::LeaveQueue => {
+                self.server.do_send(LeaveQueueMessage { addr: ctx.address() });
+                return;
+            }
+
+            ClientMessage::SetStateMode { diff } => {
+                self.state_sync.set_diff_enabled(diff);
+                return;
+            }
+
+            ClientMessage::AckState { sequence } => {
+                if !self.state_sync.acknowledge(sequence) {
+                    self.resync(ctx);
+                }
+                return;
+            }
+
+            ClientMessage::Resync => {
+                self.resync(ctx);
+                return;
+            }
+
+            ClientMessage::WatchReplay { replay, speed } => {
+                if let Some(speed) = speed {
+                    *self.replay_speed.write().unwrap() = speed;
+                }
+                self.watch_replay(replay, ctx);
+                return;
+            }
+
+            ClientMessage::SetReplaySpeed { speed } => {
+                *self.replay_speed.write().unwrap() = speed;
+                return;
+            }
+
+            msg => msg,
+        };
+
+        if self.spectator_delay.is_some() {
+            PlayerSession::send_error(ProtocolError::SpectatorsCannotAct, "Spectators cannot act".to_string(), ctx);
+            return;
+        }
+
+        if self.game_loop_channel.is_none() {
+            PlayerSession::send_error(ProtocolError::NotInRoom, "Join a room first".to_string(), ctx);
+            return;
+        }
+
+        let id = self.player_index;
+        let event = match msg {
+            ClientMessage::SwapCards { cards } => SwapCards(id, cards),
+            ClientMessage::Reroll { dices } => RerollDice(id, dices),
+            ClientMessage::UseSkill { skill, dices } => UseSkill(id, skill, dices),
+            ClientMessage::PlayCard { card, target, dices } => UseActionCard(id, card, target, dices),
+            ClientMessage::Switch { character, dices } => ChangeActive(id, character, dices),
+            ClientMessage::ChooseActive { character } => ChooseActive(id, character),
+            ClientMessage::Tune { card, dice } => Tune(id, card, dice),
+            ClientMessage::DeclareEnd => DeclareRoundEnd(id),
+            ClientMessage::Concede => Concede(id),
+            _ => return,
+        };
+
+        self.send_event(event);
+    }
+
+    fn send_event(&self, event: GameEvent) {
+        if let Some(channel) = self.game_loop_channel.as_ref() {
+            if channel.send(event).is_err() {
+                println!("Game loop of player {} has stopped", self.player_index);
+            }
+        }
+    }
+
     pub fn message_to_json<T>(ty: &str, msg: T) -> Value where T: Serialize {
         let data = serde_json::to_value(&msg).unwrap();
         json!({
@@ -53,6 +354,16 @@ impl PlayerSession {
 
 impl Actor for PlayerSession {
     type Context = ws::WebsocketContext<Self>;
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        if let Some(room_id) = self.room_id {
+            self.server.do_send(SessionClosedMessage {
+                addr: ctx.address(),
+                room_id,
+                player_index: self.player_index,
+            });
+        }
+    }
 }
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PlayerSession {
@@ -60,17 +371,16 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PlayerSession {
         match msg {
             Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
             Ok(ws::Message::Text(text)) => {
-                let json: Value = serde_json::from_str(text.to_string().as_str()).unwrap();
-                let ty = json["type"].as_str().unwrap();
-                println!("Client message: {}\nType: {}", text.to_string(), ty);
-                match ty {
-                    "JoinRoom" => {
-                        let room_id = json["room"].as_u64().unwrap();
-                        self.join_room(room_id as usize, ctx);
+                println!("Client message: {}", text);
+                let msg = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        PlayerSession::send_error(ProtocolError::Malformed, e.to_string(), ctx);
+                        return;
                     }
+                };
 
-                    _ => {}
-                }
+                self.handle_client_message(msg, ctx);
             }
             _ => (),
         }
@@ -81,8 +391,8 @@ impl Handler<SetupClientMessage> for PlayerSession {
     type Result = ();
 
     fn handle(&mut self, msg: SetupClientMessage, ctx: &mut Self::Context) -> Self::Result {
-        let json= PlayerSession::message_to_json("SetupClient", msg);
-        println!("Server: {}", json.to_string());
+        let json = PlayerSession::message_to_json("SetupClient", msg);
+        println!("Server: {}", json);
         ctx.text(json.to_string());
     }
 }
@@ -91,8 +401,8 @@ impl Handler<TurnOfMessage> for PlayerSession {
     type Result = ();
 
     fn handle(&mut self, msg: TurnOfMessage, ctx: &mut Self::Context) -> Self::Result {
-        let json= PlayerSession::message_to_json("TurnOf", msg);
-        println!("Server: {}", json.to_string());
+        let json = PlayerSession::message_to_json("TurnOf", msg);
+        println!("Server: {}", json);
         ctx.text(json.to_string());
     }
 }
@@ -101,8 +411,197 @@ impl Handler<UpdateDicesMessage> for PlayerSession {
     type Result = ();
 
     fn handle(&mut self, msg: UpdateDicesMessage, ctx: &mut Self::Context) -> Self::Result {
-        let json= PlayerSession::message_to_json("UpdateDices", msg);
-        println!("Server: {}", json.to_string());
+        let json = PlayerSession::message_to_json("UpdateDices", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<DamageMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: DamageMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("Damage", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<CharacterDefeatedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: CharacterDefeatedMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("CharacterDefeated", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<ChooseActiveMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: ChooseActiveMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("ChooseActive", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<GameOverMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: GameOverMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("GameOver", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<ActionRejectedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: ActionRejectedMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("ActionRejected", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<UpdateEnergyMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateEnergyMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("UpdateEnergy", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<JoinRejectedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: JoinRejectedMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("JoinRejected", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<UpdateHandMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateHandMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("UpdateHand", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<CardPlayedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: CardPlayedMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("CardPlayed", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<UpdateStatusesMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateStatusesMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("UpdateStatuses", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<CharacterSwitchedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: CharacterSwitchedMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("CharacterSwitched", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<UpdateStateMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: UpdateStateMessage, ctx: &mut Self::Context) -> Self::Result {
+        let state = serde_json::to_value(&msg).unwrap();
+        match self.spectator_delay {
+            Some(delay) if !delay.is_zero() => {
+                ctx.run_later(delay, move |act, ctx| {
+                    let frame = act.state_sync.next_frame(state);
+                    PlayerSession::send_frame(frame, ctx);
+                });
+            }
+            _ => {
+                let frame = self.state_sync.next_frame(state);
+                PlayerSession::send_frame(frame, ctx);
+            }
+        }
+    }
+}
+
+impl Handler<ConnectionStatusMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: ConnectionStatusMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("ConnectionStatus", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+    }
+}
+
+impl Handler<MatchedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: MatchedMessage, ctx: &mut Self::Context) -> Self::Result {
+        // The session got into another room while queued, the match is given up
+        if self.room_id.is_some() || self.spectator_delay.is_some() {
+            println!("Session is already in a room, conceding the quick match");
+            msg.result.sender.send(Concede(msg.result.player_index)).ok();
+            return;
+        }
+
+        let json = PlayerSession::message_to_json("MatchFound", MatchFoundMessage {
+            room_id: msg.result.room_id,
+            player_index: msg.result.player_index,
+        });
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+
+        let event = SetupClient(msg.result.player_index);
+        self.bind_room(msg.result, event, ctx);
+    }
+}
+
+impl Handler<SessionReplacedMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionReplacedMessage, ctx: &mut Self::Context) -> Self::Result {
+        // Unbound first, so closing does not report the player as disconnected
+        self.game_loop_channel = None;
+        self.room_id = None;
+
+        let json = PlayerSession::message_to_json("SessionReplaced", msg);
+        println!("Server: {}", json);
+        ctx.text(json.to_string());
+        ctx.close(None);
+        ctx.stop();
+    }
+}
+
+impl Handler<ClockTickMessage> for PlayerSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClockTickMessage, ctx: &mut Self::Context) -> Self::Result {
+        let json = PlayerSession::message_to_json("ClockTick", msg);
+        println!("Server: {}", json);
         ctx.text(json.to_string());
     }
 }
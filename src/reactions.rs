@@ -0,0 +1,389 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use int_enum::IntEnum;
+use serde::Serialize;
+use crate::cards::SummonedCard;
+use crate::damage::{CharacterRef, DamageEvent, DamageKind, DamageSource, ModifierStage};
+use crate::dice_set::ElementType;
+use crate::game_environment::GameEnvironment;
+use crate::status::{Status, StatusCounter, StatusOwner};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum DamageType {
+    Physical,
+    Piercing,
+    Elemental(ElementType),
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum Reaction {
+    Melt,
+    Vaporize,
+    Overloaded,
+    Superconduct,
+    ElectroCharged,
+    Frozen,
+    // Element swirled
+    Swirl(ElementType),
+    // Element crystallized
+    Crystallize(ElementType),
+    Bloom,
+    Quicken,
+    Burning,
+}
+
+impl Reaction {
+    pub fn bonus_damage(&self) -> usize {
+        match self {
+            Reaction::Melt | Reaction::Vaporize | Reaction::Overloaded => 2,
+            Reaction::Swirl(_) => 0,
+            _ => 1,
+        }
+    }
+}
+
+fn is_swirlable(element: ElementType) -> bool {
+    matches!(element, ElementType::Pyro | ElementType::Hydro | ElementType::Electro | ElementType::Cryo)
+}
+
+fn react(applied: ElementType, incoming: ElementType) -> Option<Reaction> {
+    use ElementType::*;
+
+    let pair = if applied.int_value() < incoming.int_value() {
+        (applied, incoming)
+    } else {
+        (incoming, applied)
+    };
+
+    match pair {
+        (Pyro, Cryo) => Some(Reaction::Melt),
+        (Hydro, Pyro) => Some(Reaction::Vaporize),
+        (Electro, Pyro) => Some(Reaction::Overloaded),
+        (Electro, Cryo) => Some(Reaction::Superconduct),
+        (Electro, Hydro) => Some(Reaction::ElectroCharged),
+        (Hydro, Cryo) => Some(Reaction::Frozen),
+        (Hydro, Dendro) => Some(Reaction::Bloom),
+        (Pyro, Dendro) => Some(Reaction::Burning),
+        (Electro, Dendro) => Some(Reaction::Quicken),
+        _ => None,
+    }
+}
+
+// Applies an incoming element to the elements attached to a character
+// Returns the reaction triggered, the consumed aura is removed
+pub fn apply_element(applied: &mut Vec<ElementType>, incoming: ElementType) -> Option<Reaction> {
+    if incoming == ElementType::Anemo || incoming == ElementType::Geo {
+        // Anemo and Geo never stay attached
+        let index = applied.iter().position(|a| is_swirlable(*a))?;
+        let element = applied.remove(index);
+
+        return if incoming == ElementType::Anemo {
+            Some(Reaction::Swirl(element))
+        } else {
+            Some(Reaction::Crystallize(element))
+        };
+    }
+
+    for i in 0..applied.len() {
+        if let Some(reaction) = react(applied[i], incoming) {
+            applied.remove(i);
+            return Some(reaction);
+        }
+    }
+
+    // Only Cryo and Dendro can coexist, other elements can only refresh the aura
+    if !applied.contains(&incoming) {
+        applied.push(incoming);
+    }
+
+    None
+}
+
+pub struct BurningFlame {
+    usages: AtomicUsize,
+}
+
+impl BurningFlame {
+    pub const MAX_USAGES: usize = 2;
+
+    pub fn new() -> Self {
+        BurningFlame {
+            usages: AtomicUsize::new(1),
+        }
+    }
+}
+
+impl SummonedCard for BurningFlame {
+    fn name(&self) -> &'static str {
+        "Burning Flame"
+    }
+
+    fn on_turn_end(&self, subject_player: usize, env: &mut GameEnvironment) {
+        let target_player = 1 - subject_player;
+        env.deal_damage(
+            DamageSource { player: subject_player, character: None },
+            CharacterRef { player: target_player, character: env.players[target_player].active_character },
+            1,
+            DamageType::Elemental(ElementType::Pyro),
+            DamageKind::Summon,
+        );
+        self.usages.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn remaining_uses(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+
+    fn refresh(&self) {
+        let usages = self.usages.load(Ordering::Relaxed);
+        self.usages.store((usages + 1).min(BurningFlame::MAX_USAGES), Ordering::Relaxed);
+    }
+}
+
+fn is_elemental(event: &DamageEvent, elements: &[ElementType]) -> bool {
+    match event.damage_type {
+        DamageType::Elemental(element) => elements.contains(&element),
+        _ => false,
+    }
+}
+
+// The character cannot use skills until the end of the round
+// Physical or Pyro damage shatters the ice for +2 damage
+pub struct Frozen {
+    counter: StatusCounter,
+}
+
+impl Frozen {
+    pub const NAME: &'static str = "Frozen";
+
+    pub fn new() -> Self {
+        Frozen {
+            counter: StatusCounter::new(1, 1),
+        }
+    }
+}
+
+impl Status for Frozen {
+    fn name(&self) -> &'static str {
+        Frozen::NAME
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        let shatters = event.damage_type == DamageType::Physical
+            || event.damage_type == DamageType::Elemental(ElementType::Pyro);
+        if event.target.player == owner.player && Some(event.target.character) == owner.character && shatters {
+            event.amount += 2;
+            self.counter.consume();
+        }
+    }
+
+    fn refresh(&self) {
+        self.counter.set_rounds(1);
+    }
+}
+
+// +2 to the next Pyro or Electro damage dealt, created by Bloom
+pub struct DendroCore {
+    counter: StatusCounter,
+}
+
+impl DendroCore {
+    pub fn new() -> Self {
+        DendroCore {
+            counter: StatusCounter::new(1, StatusCounter::UNLIMITED),
+        }
+    }
+}
+
+impl Status for DendroCore {
+    fn name(&self) -> &'static str {
+        "Dendro Core"
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        if event.source.player == owner.player && !self.expired()
+            && is_elemental(event, &[ElementType::Pyro, ElementType::Electro]) {
+            event.amount += 2;
+            self.counter.consume();
+        }
+    }
+}
+
+// +1 to Electro or Dendro damage dealt, created by Quicken
+pub struct CatalyzingField {
+    counter: StatusCounter,
+}
+
+impl CatalyzingField {
+    pub const MAX_USAGES: usize = 2;
+
+    pub fn new() -> Self {
+        CatalyzingField {
+            counter: StatusCounter::new(CatalyzingField::MAX_USAGES, StatusCounter::UNLIMITED),
+        }
+    }
+}
+
+impl Status for CatalyzingField {
+    fn name(&self) -> &'static str {
+        "Catalyzing Field"
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        if event.source.player == owner.player && !self.expired()
+            && is_elemental(event, &[ElementType::Electro, ElementType::Dendro]) {
+            event.amount += 1;
+            self.counter.consume();
+        }
+    }
+
+    fn refresh(&self) {
+        self.counter.set_usages(CatalyzingField::MAX_USAGES);
+    }
+}
+
+// Shield protecting the active character, created by Crystallize
+pub struct CrystallizeShield {
+    counter: StatusCounter,
+}
+
+impl CrystallizeShield {
+    pub const MAX_POINTS: usize = 2;
+
+    pub fn new() -> Self {
+        CrystallizeShield {
+            counter: StatusCounter::new(1, StatusCounter::UNLIMITED),
+        }
+    }
+}
+
+impl Status for CrystallizeShield {
+    fn name(&self) -> &'static str {
+        "Crystallize"
+    }
+
+    fn counter(&self) -> &StatusCounter {
+        &self.counter
+    }
+
+    fn stage(&self) -> ModifierStage {
+        ModifierStage::Shield
+    }
+
+    fn before_damage(&self, owner: StatusOwner, event: &mut DamageEvent) {
+        if event.target.player == owner.player && event.target_active {
+            let points = self.counter.usages();
+            let absorbed = points.min(event.amount);
+            self.counter.set_usages(points - absorbed);
+            event.amount -= absorbed;
+            event.absorbed += absorbed;
+        }
+    }
+
+    fn refresh(&self) {
+        self.counter.add_usages(1, CrystallizeShield::MAX_POINTS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ElementType::*;
+
+    fn reaction_of(applied: ElementType, incoming: ElementType) -> Option<Reaction> {
+        let mut elements = vec![applied];
+        apply_element(&mut elements, incoming)
+    }
+
+    #[test]
+    fn every_pair_reacts_in_both_orders() {
+        let pairs = [
+            (Pyro, Cryo, Reaction::Melt),
+            (Hydro, Pyro, Reaction::Vaporize),
+            (Electro, Pyro, Reaction::Overloaded),
+            (Electro, Cryo, Reaction::Superconduct),
+            (Electro, Hydro, Reaction::ElectroCharged),
+            (Hydro, Cryo, Reaction::Frozen),
+            (Hydro, Dendro, Reaction::Bloom),
+            (Pyro, Dendro, Reaction::Burning),
+            (Electro, Dendro, Reaction::Quicken),
+        ];
+
+        for (a, b, reaction) in pairs {
+            assert_eq!(reaction_of(a, b), Some(reaction), "{} on {}", b, a);
+            assert_eq!(reaction_of(b, a), Some(reaction), "{} on {}", a, b);
+        }
+    }
+
+    #[test]
+    fn reaction_consumes_the_aura() {
+        let mut elements = vec![Pyro];
+        assert_eq!(apply_element(&mut elements, Hydro), Some(Reaction::Vaporize));
+        assert!(elements.is_empty());
+    }
+
+    #[test]
+    fn same_element_refreshes_the_aura() {
+        for element in [Pyro, Hydro, Electro, Cryo, Dendro] {
+            let mut elements = vec![element];
+            assert_eq!(apply_element(&mut elements, element), None);
+            assert_eq!(elements, vec![element]);
+        }
+    }
+
+    #[test]
+    fn cryo_and_dendro_coexist() {
+        let mut elements = vec![Cryo];
+        assert_eq!(apply_element(&mut elements, Dendro), None);
+        assert_eq!(elements, vec![Cryo, Dendro]);
+
+        // The first attached element reacts first
+        assert_eq!(apply_element(&mut elements, Pyro), Some(Reaction::Melt));
+        assert_eq!(elements, vec![Dendro]);
+
+        let mut elements = vec![Dendro];
+        assert_eq!(apply_element(&mut elements, Cryo), None);
+        assert_eq!(elements, vec![Dendro, Cryo]);
+    }
+
+    #[test]
+    fn anemo_and_geo() {
+        for element in [Pyro, Hydro, Electro, Cryo] {
+            let mut elements = vec![element];
+            assert_eq!(apply_element(&mut elements, Anemo), Some(Reaction::Swirl(element)));
+            assert!(elements.is_empty());
+
+            let mut elements = vec![element];
+            assert_eq!(apply_element(&mut elements, Geo), Some(Reaction::Crystallize(element)));
+            assert!(elements.is_empty());
+        }
+
+        // Nothing to react with, and neither element stays attached
+        for incoming in [Anemo, Geo] {
+            let mut elements = vec![];
+            assert_eq!(apply_element(&mut elements, incoming), None);
+            assert!(elements.is_empty());
+
+            let mut elements = vec![Dendro];
+            assert_eq!(apply_element(&mut elements, incoming), None);
+            assert_eq!(elements, vec![Dendro]);
+        }
+    }
+
+    #[test]
+    fn unrelated_elements_do_not_react() {
+        assert_eq!(reaction_of(Cryo, Dendro), None);
+        assert_eq!(reaction_of(Dendro, Cryo), None);
+    }
+}
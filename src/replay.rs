@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix::Addr;
+use serde::{Deserialize, Serialize};
+use crate::characters::registry::CharacterRegistry;
+use crate::deck::Deck;
+use crate::game_environment::{GameEnvironment, GameResult};
+use crate::game_events::GameEvent;
+use crate::player::Player;
+use crate::player_session::PlayerSession;
+
+pub const REPLAY_DIR: &str = "replays";
+
+// Time between two events at speed 1
+const STEP_MILLIS: f64 = 1000.0;
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub decks: Vec<Deck>,
+    pub events: Vec<GameEvent>,
+    pub result: Option<GameResult>,
+}
+
+impl Replay {
+    pub fn from_game(env: &GameEnvironment) -> Self {
+        Replay {
+            seed: env.seed,
+            decks: env.players[0..env.active_player_count].iter().map(|a| a.deck.clone()).collect(),
+            events: env.history.clone(),
+            result: env.result,
+        }
+    }
+
+    // Returns the file name of the replay
+    pub fn save(&self, room_id: usize) -> Result<String, String> {
+        fs::create_dir_all(REPLAY_DIR).map_err(|e| e.to_string())?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|a| a.as_secs()).unwrap_or(0);
+        let name = format!("room-{}-{}.json", room_id, timestamp);
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(Path::new(REPLAY_DIR).join(&name), json).map_err(|e| e.to_string())?;
+
+        Ok(name)
+    }
+
+    pub fn load(name: &str) -> Result<Self, String> {
+        // Only files directly in the replay directory can be loaded
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(format!("Invalid replay name {}", name));
+        }
+
+        let path: PathBuf = Path::new(REPLAY_DIR).join(name);
+        let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    // Environment the recorded game started from, without sessions
+    fn new_game(&self, registry: &CharacterRegistry) -> GameEnvironment {
+        let mut env = GameEnvironment::new(registry, self.seed);
+        for deck in self.decks.iter() {
+            env.add_player(None, Player::new(registry, deck));
+        }
+        env
+    }
+
+    // Re-runs the game and streams the state after every event to the viewer
+    // Speed is read before every step so the viewer can change it during playback
+    pub fn play(self, registry: Arc<CharacterRegistry>, viewer: Addr<PlayerSession>, speed: Arc<RwLock<f64>>) {
+        thread::spawn(move || {
+            let mut env = self.new_game(&registry);
+
+            // Events the game would send to itself are already part of the recording
+            let (send, recv) = channel();
+            for event in self.events.iter() {
+                if !viewer.connected() {
+                    break;
+                }
+
+                env.handle_message(event, &send);
+                while recv.try_recv().is_ok() {}
+
+                viewer.do_send(env.state_view(0, [true, true]));
+
+                let speed = speed.read().map(|a| *a).unwrap_or(1.0).max(0.1);
+                thread::sleep(Duration::from_millis((STEP_MILLIS / speed) as u64));
+            }
+
+            println!("Replay playback finished");
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use super::*;
+    use crate::characters::registry::CHARACTER_DATA_DIR;
+
+    // Plays a game with random legal actions, events the game sends to itself are handled first
+    fn play_game(registry: &CharacterRegistry, seed: u64) -> GameEnvironment {
+        let replay = Replay { seed, decks: vec![Deck::default(), Deck::default()], events: vec![], result: None };
+        let mut env = replay.new_game(registry);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (send, recv) = channel();
+        send.send(GameEvent::SetupClient(0)).unwrap();
+        send.send(GameEvent::SetupClient(1)).unwrap();
+
+        for _ in 0..5000 {
+            if env.result.is_some() {
+                break;
+            }
+
+            match recv.try_recv() {
+                Ok(event) => env.handle_message(&event, &send),
+                Err(_) => {
+                    let index = (0..2).find(|a| !env.legal_actions(*a).is_empty()).expect("Game is stuck");
+                    let action = env.legal_actions(index).choose(&mut rng).unwrap().clone();
+                    env.handle_message(&action, &send);
+                }
+            }
+        }
+        env
+    }
+
+    fn replay_game(registry: &CharacterRegistry, replay: &Replay) -> GameEnvironment {
+        let mut env = replay.new_game(registry);
+        let (send, recv) = channel();
+        for event in replay.events.iter() {
+            env.handle_message(event, &send);
+            while recv.try_recv().is_ok() {}
+        }
+        env
+    }
+
+    fn state(env: &GameEnvironment) -> serde_json::Value {
+        serde_json::to_value(env.state_view(0, [true, true])).unwrap()
+    }
+
+    #[test]
+    fn replay_reaches_the_same_state() {
+        let registry = CharacterRegistry::load(CHARACTER_DATA_DIR).unwrap();
+        for seed in [1, 2, 3] {
+            let game = play_game(&registry, seed);
+            assert!(game.result.is_some(), "Game with seed {} did not finish", seed);
+
+            let replay = Replay::from_game(&game);
+            let replayed = replay_game(&registry, &replay);
+            assert_eq!(replayed.history.len(), game.history.len());
+            assert_eq!(replayed.result, game.result);
+            assert_eq!(state(&replayed), state(&game));
+
+            // The recording survives saving and loading
+            let loaded: Replay = serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+            assert_eq!(state(&replay_game(&registry, &loaded)), state(&game));
+        }
+    }
+}
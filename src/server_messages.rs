@@ -1,15 +1,46 @@
 use actix::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use crate::client_messages::ProtocolError;
+use crate::damage::DamageResult;
+use crate::dice_set::ElementType;
+use crate::game_server::JoinError;
+use crate::lobby::RoomInfo;
+use crate::phase::Phase;
+use crate::status::StatusInfo;
+use crate::validation::RejectReason;
 
 // Messages sent to the client
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
+pub struct CharacterState {
+    pub name: String,
+    pub weapon: String,
+    pub faction: String,
+    pub skills: Vec<String>,
+    pub hp: usize,
+    pub max_hp: usize,
+    pub energy: usize,
+    pub max_energy: usize,
+    pub applied_elements: Vec<ElementType>,
+    pub statuses: Vec<StatusInfo>,
+    pub defeated: bool,
+}
+
+// Hidden information is None when the state is sent to the other player
+#[derive(Serialize, Clone)]
 pub struct PlayerState {
-    hp: usize,
-    support_area: Vec<(String, usize)>,
-    summoned_area: Vec<(String, usize)>,
-    active_character: usize,
-    dice_set: Vec<usize>,
+    pub characters: Vec<CharacterState>,
+    pub active_character: usize,
+    pub combat_statuses: Vec<StatusInfo>,
+    // Name and remaining usages
+    pub support_area: Vec<(String, usize)>,
+    pub summoned_area: Vec<(String, usize)>,
+    pub hand: Option<Vec<String>>,
+    pub hand_size: usize,
+    pub draw_pile_size: usize,
+    pub dice_set: Option<Vec<i8>>,
+    pub dice_count: usize,
+    pub declared_end: bool,
 }
 
 #[derive(Serialize, Message)]
@@ -18,6 +49,8 @@ pub struct UpdateStateMessage {
     pub player_state: PlayerState,
     pub opponent_state: PlayerState,
     pub players_turn: bool,
+    pub phase: Phase,
+    pub round: usize,
 }
 
 #[derive(Serialize, Message)]
@@ -38,4 +71,141 @@ pub struct UpdateDicesMessage {
 #[rtype(result = "()")]
 pub struct TurnOfMessage{
     pub turn_of: usize
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct DamageMessage {
+    pub result: DamageResult,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct CharacterDefeatedMessage {
+    pub player_index: usize,
+    pub character_index: usize,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct ChooseActiveMessage {
+    pub available_characters: Vec<usize>,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub enum GameOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct GameOverMessage {
+    pub outcome: GameOutcome,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct ActionRejectedMessage {
+    pub reason: RejectReason,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct UpdateEnergyMessage {
+    pub player_index: usize,
+    pub energy: Vec<usize>,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct JoinRejectedMessage {
+    pub reason: JoinError,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct UpdateHandMessage {
+    pub cards: Vec<String>,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct CardPlayedMessage {
+    pub player_index: usize,
+    pub card: String,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct UpdateStatusesMessage {
+    pub player_index: usize,
+    pub combat_statuses: Vec<StatusInfo>,
+    pub character_statuses: Vec<Vec<StatusInfo>>,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct CharacterSwitchedMessage {
+    pub player_index: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Serialize)]
+pub struct WelcomeMessage {
+    pub protocol_version: u32,
+}
+
+#[derive(Serialize)]
+pub struct ErrorMessage {
+    pub error: ProtocolError,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct JoinedRoomMessage {
+    pub room_id: usize,
+    pub player_index: usize,
+    pub reconnect_token: String,
+}
+
+// Sent to a session whose player was resumed from another session, which closes it
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct SessionReplacedMessage {
+    pub room_id: usize,
+    pub player_index: usize,
+}
+
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct ConnectionStatusMessage {
+    pub player_index: usize,
+    pub connected: bool,
+    // Seconds a disconnected player has to come back
+    pub grace_period: u64,
+}
+
+#[derive(Serialize)]
+pub struct MatchFoundMessage {
+    pub room_id: usize,
+    pub player_index: usize,
+}
+
+#[derive(Serialize)]
+pub struct RoomListMessage {
+    pub rooms: Vec<RoomInfo>,
+}
+
+// Sent every second while the game waits for a player with a time limit
+#[derive(Serialize, Message)]
+#[rtype(result = "()")]
+pub struct ClockTickMessage {
+    pub player_index: usize,
+    // Seconds left, None without a limit
+    pub action_remaining: Option<u64>,
+    pub round_remaining: Option<u64>,
+    pub timeouts: usize,
+}
@@ -0,0 +1,73 @@
+use crate::game_environment::GameEnvironment;
+use crate::phase::Phase;
+use crate::server_messages::{CharacterState, PlayerState, UpdateStateMessage};
+
+impl GameEnvironment {
+    // State of a player as seen by a viewer, the hand and dice are only revealed to the player themselves
+    pub fn player_state(&self, index: usize, reveal: bool) -> PlayerState {
+        let player = &self.players[index];
+
+        PlayerState {
+            characters: player.characters.iter().map(|a| CharacterState {
+                name: a.name.clone(),
+                weapon: a.weapon.clone(),
+                faction: a.faction.clone(),
+                skills: a.skill_names.to_vec(),
+                hp: a.hp,
+                max_hp: a.max_hp,
+                energy: a.energy,
+                max_energy: a.max_energy,
+                applied_elements: a.applied_elements.clone(),
+                statuses: a.statuses.iter().map(|b| b.info()).collect(),
+                defeated: a.defeated,
+            }).collect(),
+            active_character: player.active_character,
+            combat_statuses: player.combat_statuses.iter().map(|a| a.info()).collect(),
+            support_area: player.support_area[0..player.support_area_count].iter()
+                .map(|a| (a.name().to_string(), a.remaining_uses()))
+                .collect(),
+            summoned_area: player.summoned_area[0..player.summoned_area_count].iter()
+                .map(|a| (a.name().to_string(), a.remaining_uses()))
+                .collect(),
+            hand: if reveal {
+                Some(player.hand.iter().map(|a| a.name().to_string()).collect())
+            } else {
+                None
+            },
+            hand_size: player.hand.len(),
+            draw_pile_size: player.draw_pile.len(),
+            dice_set: if reveal { Some(player.dice_set.to_vec()) } else { None },
+            dice_count: player.dice_set.dice_count,
+            declared_end: self.declared_end[index],
+        }
+    }
+
+    pub fn state_for(&self, viewer: usize) -> UpdateStateMessage {
+        self.state_view(viewer, [viewer == 0, viewer == 1])
+    }
+
+    // State from the side of a player, hidden information is revealed for the players set in reveal
+    pub fn state_view(&self, viewer: usize, reveal: [bool; 2]) -> UpdateStateMessage {
+        UpdateStateMessage {
+            player_state: self.player_state(viewer, reveal[viewer]),
+            opponent_state: self.player_state(1 - viewer, reveal[1 - viewer]),
+            players_turn: self.phase == Phase::Action && self.current_turn == viewer,
+            phase: self.phase,
+            round: self.round,
+        }
+    }
+
+    // Sends every player a snapshot of the game from their side, spectators see the game from the first player's side
+    pub fn broadcast_state(&mut self) {
+        for (index, addr) in self.session_addr.iter().enumerate() {
+            if let Some(addr) = addr {
+                addr.do_send(self.state_for(index));
+            }
+        }
+
+        self.spectators.retain(|(addr, _)| addr.connected());
+        for (addr, full) in self.spectators.iter() {
+            addr.do_send(self.state_view(0, [*full, *full]));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use serde_json::{json, Map, Value};
+
+// States kept around until the client acknowledges one of them
+const MAX_UNACKNOWLEDGED: usize = 32;
+
+// Tracks the state frames sent to one client
+// Full snapshots are sent until the client opts into diffs and acknowledges a state,
+// from then on patches are computed against the last acknowledged state
+#[derive(Debug, Default)]
+pub struct StateSync {
+    diff_enabled: bool,
+    sequence: u64,
+    acknowledged: Option<(u64, Value)>,
+    unacknowledged: VecDeque<(u64, Value)>,
+}
+
+impl StateSync {
+    pub fn new() -> Self {
+        StateSync::default()
+    }
+
+    pub fn set_diff_enabled(&mut self, enabled: bool) {
+        self.diff_enabled = enabled;
+        if !enabled {
+            self.acknowledged = None;
+        }
+    }
+
+    // Frame sent to the client for a new state
+    pub fn next_frame(&mut self, state: Value) -> Value {
+        self.sequence += 1;
+        self.unacknowledged.push_back((self.sequence, state.clone()));
+        if self.unacknowledged.len() > MAX_UNACKNOWLEDGED {
+            self.unacknowledged.pop_front();
+        }
+
+        match self.acknowledged.as_ref() {
+            Some((base, base_state)) if self.diff_enabled => json!({
+                "type": "StatePatch",
+                "data": {
+                    "sequence": self.sequence,
+                    "base": base,
+                    "patch": diff(base_state, &state),
+                }
+            }),
+            _ => StateSync::full_frame(self.sequence, state),
+        }
+    }
+
+    // Returns false if the state is unknown, the client should resync then
+    pub fn acknowledge(&mut self, sequence: u64) -> bool {
+        let position = match self.unacknowledged.iter().position(|(a, _)| *a == sequence) {
+            Some(position) => position,
+            None => return self.acknowledged.as_ref().map(|(a, _)| *a) == Some(sequence),
+        };
+
+        self.unacknowledged.drain(0..position);
+        self.acknowledged = self.unacknowledged.pop_front();
+        true
+    }
+
+    // Full snapshot of the latest state under a new sequence number, diffs restart from it once acknowledged
+    pub fn resync_frame(&mut self) -> Option<Value> {
+        let state = self.unacknowledged.back().or(self.acknowledged.as_ref())?.1.clone();
+        self.acknowledged = None;
+        self.unacknowledged.clear();
+
+        self.sequence += 1;
+        self.unacknowledged.push_back((self.sequence, state.clone()));
+        Some(StateSync::full_frame(self.sequence, state))
+    }
+
+    fn full_frame(sequence: u64, state: Value) -> Value {
+        let mut data = state;
+        if let Value::Object(map) = &mut data {
+            map.insert("sequence".to_string(), json!(sequence));
+        }
+
+        json!({
+            "type": "UpdateState",
+            "data": data
+        })
+    }
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// JSON Patch (RFC 6902) operations turning old into new
+// Arrays that change length are replaced as a whole
+fn diff(old: &Value, new: &Value) -> Vec<Value> {
+    let mut patch = vec![];
+    diff_into(old, new, String::new(), &mut patch);
+    patch
+}
+
+fn diff_into(old: &Value, new: &Value, path: String, patch: &mut Vec<Value>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(old, new, path, patch),
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (i, (a, b)) in old.iter().zip(new.iter()).enumerate() {
+                diff_into(a, b, format!("{}/{}", path, i), patch);
+            }
+        }
+        _ if old != new => {
+            patch.push(json!({ "op": "replace", "path": path, "value": new }));
+        }
+        _ => {}
+    }
+}
+
+fn diff_objects(old: &Map<String, Value>, new: &Map<String, Value>, path: String, patch: &mut Vec<Value>) {
+    for (key, value) in new.iter() {
+        let child = format!("{}/{}", path, escape_token(key));
+        match old.get(key) {
+            Some(old_value) => diff_into(old_value, value, child, patch),
+            None => patch.push(json!({ "op": "add", "path": child, "value": value })),
+        }
+    }
+
+    for key in old.keys().filter(|a| !new.contains_key(*a)) {
+        patch.push(json!({ "op": "remove", "path": format!("{}/{}", path, escape_token(key)) }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(hp: u64) -> Value {
+        json!({ "hp": hp, "name": "Diluc" })
+    }
+
+    #[test]
+    fn full_frames_until_acknowledged() {
+        let mut sync = StateSync::new();
+        sync.set_diff_enabled(true);
+
+        let frame = sync.next_frame(state(10));
+        assert_eq!(frame["type"], "UpdateState");
+        assert_eq!(frame["data"]["sequence"], 1);
+        assert_eq!(frame["data"]["hp"], 10);
+
+        let frame = sync.next_frame(state(9));
+        assert_eq!(frame["type"], "UpdateState");
+        assert_eq!(frame["data"]["sequence"], 2);
+    }
+
+    #[test]
+    fn full_frames_without_diffs() {
+        let mut sync = StateSync::new();
+        sync.next_frame(state(10));
+        assert!(sync.acknowledge(1));
+
+        let frame = sync.next_frame(state(9));
+        assert_eq!(frame["type"], "UpdateState");
+    }
+
+    #[test]
+    fn patches_against_acknowledged_state() {
+        let mut sync = StateSync::new();
+        sync.set_diff_enabled(true);
+        sync.next_frame(state(10));
+        sync.next_frame(state(9));
+        assert!(sync.acknowledge(2));
+
+        let frame = sync.next_frame(state(7));
+        assert_eq!(frame["type"], "StatePatch");
+        assert_eq!(frame["data"]["sequence"], 3);
+        assert_eq!(frame["data"]["base"], 2);
+        assert_eq!(frame["data"]["patch"], json!([{ "op": "replace", "path": "/hp", "value": 7 }]));
+
+        // Unacknowledged frames don't move the base
+        let frame = sync.next_frame(state(5));
+        assert_eq!(frame["data"]["sequence"], 4);
+        assert_eq!(frame["data"]["base"], 2);
+    }
+
+    #[test]
+    fn acknowledge_unknown_sequence() {
+        let mut sync = StateSync::new();
+        sync.set_diff_enabled(true);
+        sync.next_frame(state(10));
+
+        assert!(!sync.acknowledge(5));
+        assert!(sync.acknowledge(1));
+        // The acknowledged state itself can be acknowledged again
+        assert!(sync.acknowledge(1));
+        assert!(!sync.acknowledge(0));
+    }
+
+    #[test]
+    fn older_states_are_dropped_on_acknowledge() {
+        let mut sync = StateSync::new();
+        sync.set_diff_enabled(true);
+        sync.next_frame(state(10));
+        sync.next_frame(state(9));
+        assert!(sync.acknowledge(2));
+        assert!(!sync.acknowledge(1));
+    }
+
+    #[test]
+    fn resync_restarts_from_full_frame() {
+        let mut sync = StateSync::new();
+        sync.set_diff_enabled(true);
+        assert!(sync.resync_frame().is_none());
+
+        sync.next_frame(state(10));
+        sync.acknowledge(1);
+        sync.next_frame(state(8));
+
+        let frame = sync.resync_frame().unwrap();
+        assert_eq!(frame["type"], "UpdateState");
+        assert_eq!(frame["data"]["sequence"], 3);
+        assert_eq!(frame["data"]["hp"], 8);
+
+        // Diffs wait for the resynced state to be acknowledged
+        let frame = sync.next_frame(state(6));
+        assert_eq!(frame["type"], "UpdateState");
+        assert!(sync.acknowledge(3));
+
+        let frame = sync.next_frame(state(4));
+        assert_eq!(frame["type"], "StatePatch");
+        assert_eq!(frame["data"]["base"], 3);
+    }
+
+    #[test]
+    fn patch_adds_and_removes_keys() {
+        let old = json!({ "a/b": 1, "gone": true, "list": [1, 2] });
+        let new = json!({ "a/b": 1, "new": "x", "list": [1, 3] });
+        let patch = diff(&old, &new);
+
+        assert!(patch.contains(&json!({ "op": "add", "path": "/new", "value": "x" })));
+        assert!(patch.contains(&json!({ "op": "remove", "path": "/gone" })));
+        assert!(patch.contains(&json!({ "op": "replace", "path": "/list/1", "value": 3 })));
+        assert_eq!(patch.len(), 3);
+    }
+}
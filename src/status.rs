@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::Serialize;
+use crate::cost::{Cost, CostSubject};
+use crate::damage::{CharacterRef, DamageEvent, DamageSource, ModifierStage};
+use crate::game_environment::GameEnvironment;
+use crate::game_events::SkillType;
+use crate::hooks::HookEvent;
+use crate::reactions::Reaction;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub struct StatusOwner {
+    pub player: usize,
+    // None for combat statuses, which belong to the whole team
+    pub character: Option<usize>,
+}
+
+// Usage and round counters of a status, the status expires when either runs out
+pub struct StatusCounter {
+    usages: AtomicUsize,
+    rounds: AtomicUsize,
+}
+
+impl StatusCounter {
+    pub const UNLIMITED: usize = usize::MAX;
+
+    pub fn new(usages: usize, rounds: usize) -> Self {
+        StatusCounter {
+            usages: AtomicUsize::new(usages),
+            rounds: AtomicUsize::new(rounds),
+        }
+    }
+
+    pub fn usages(&self) -> usize {
+        self.usages.load(Ordering::Relaxed)
+    }
+
+    pub fn set_usages(&self, usages: usize) {
+        self.usages.store(usages, Ordering::Relaxed);
+    }
+
+    pub fn add_usages(&self, amount: usize, max: usize) {
+        self.set_usages((self.usages() + amount).min(max));
+    }
+
+    pub fn consume(&self) {
+        self.set_usages(self.usages().saturating_sub(1));
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rounds(&self, rounds: usize) {
+        self.rounds.store(rounds, Ordering::Relaxed);
+    }
+
+    pub fn pass_round(&self) {
+        let rounds = self.rounds();
+        if rounds != StatusCounter::UNLIMITED {
+            self.set_rounds(rounds.saturating_sub(1));
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StatusInfo {
+    pub name: String,
+    pub usages: usize,
+    // None if the status does not expire with rounds
+    pub rounds: Option<usize>,
+}
+
+pub trait Status: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn counter(&self) -> &StatusCounter;
+    // Order in which before_damage is called among the statuses involved in a damage
+    fn stage(&self) -> ModifierStage {
+        ModifierStage::Additive
+    }
+    fn before_damage(&self, _owner: StatusOwner, _event: &mut DamageEvent) {}
+    fn before_skill(&self, _owner: StatusOwner, _skill: SkillType, _env: &mut GameEnvironment) {}
+    fn after_skill(&self, _owner: StatusOwner, _skill: SkillType, _env: &mut GameEnvironment) {}
+    // Reactions triggered by either team
+    fn on_reaction(&self, _owner: StatusOwner, _reaction: Reaction, _source: DamageSource, _target: CharacterRef, _env: &mut GameEnvironment) {}
+    fn on_switch(&self, _owner: StatusOwner, _from: usize, _to: usize, _env: &mut GameEnvironment) {}
+    fn on_card_played(&self, _owner: StatusOwner, _card: &'static str, _env: &mut GameEnvironment) {}
+    fn on_dice_rolled(&self, _owner: StatusOwner, _rerolled: bool, _env: &mut GameEnvironment) {}
+    fn on_round_start(&self, _owner: StatusOwner, _round: usize, _env: &mut GameEnvironment) {}
+    fn on_round_end(&self, _owner: StatusOwner, _round: usize, _env: &mut GameEnvironment) {}
+    // Receives every hook event, events of the owner's team and round start and end are forwarded by default
+    fn on_event(&self, owner: StatusOwner, event: &HookEvent, env: &mut GameEnvironment) {
+        let own_character = |character: &CharacterRef| character.player == owner.player
+            && (owner.character.is_none() || owner.character == Some(character.character));
+
+        match event {
+            HookEvent::BeforeSkill(character, skill) if own_character(character) => self.before_skill(owner, *skill, env),
+            HookEvent::AfterSkill(character, skill) if own_character(character) => self.after_skill(owner, *skill, env),
+            HookEvent::Reaction(reaction, source, target) => self.on_reaction(owner, *reaction, *source, *target, env),
+            HookEvent::CharacterSwitched(id, from, to) if *id == owner.player => self.on_switch(owner, *from, *to, env),
+            HookEvent::CardPlayed(id, card) if *id == owner.player => self.on_card_played(owner, card, env),
+            HookEvent::DiceRolled(id, rerolled) if *id == owner.player => self.on_dice_rolled(owner, *rerolled, env),
+            HookEvent::RoundStart(round) => self.on_round_start(owner, *round, env),
+            HookEvent::RoundEnd(round) => self.on_round_end(owner, *round, env),
+            _ => {}
+        }
+    }
+    // Reduces a cost paid by the owner, returns whether the status applies
+    // A usage is consumed once the cost is paid
+    fn modify_cost(&self, _owner: StatusOwner, _subject: CostSubject, _cost: &mut Cost) -> bool {
+        false
+    }
+    // Whether the owner's next switch is a fast action, a usage is consumed when it is
+    fn fast_switch(&self, _owner: StatusOwner) -> bool {
+        false
+    }
+    // Called when the same status is added again while still active
+    fn refresh(&self) {}
+
+    fn expired(&self) -> bool {
+        self.counter().usages() == 0 || self.counter().rounds() == 0
+    }
+
+    fn info(&self) -> StatusInfo {
+        let rounds = self.counter().rounds();
+        StatusInfo {
+            name: self.name().to_string(),
+            usages: self.counter().usages(),
+            rounds: if rounds == StatusCounter::UNLIMITED { None } else { Some(rounds) },
+        }
+    }
+}
+
+// Adds a status to a status list, refreshing it instead if one with the same name exists
+pub fn add_status(statuses: &mut Vec<Arc<dyn Status>>, status: Arc<dyn Status>) {
+    if let Some(existing) = statuses.iter().find(|a| a.name() == status.name()) {
+        existing.refresh();
+    } else {
+        statuses.push(status);
+    }
+}
+
+pub fn find_status<'a>(statuses: &'a [Arc<dyn Status>], name: &str) -> Option<&'a Arc<dyn Status>> {
+    statuses.iter().find(|a| a.name() == name)
+}
@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+use serde::Serialize;
+use crate::game_environment::GameEnvironment;
+use crate::cost::Cost;
+use crate::game_events::GameEvent;
+use crate::operation_context::OperationContext;
+use crate::phase::Phase;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize)]
+pub enum RejectReason {
+    GameOver,
+    WrongPhase,
+    NotYourTurn,
+    AlreadyDeclaredEnd,
+    // A player has to choose a new active character first
+    WaitingForActiveCharacter,
+    NoRerollsLeft,
+    InvalidDice,
+    DiceCountMismatch,
+    DiceElementMismatch,
+    InvalidCharacter,
+    CharacterDefeated,
+    CharacterFrozen,
+    InsufficientEnergy,
+    InvalidCard,
+    // The card's own conditions for being played are not met
+    CardConditionNotMet,
+    AlreadySwappedCards,
+    // The die already has the element of the active character
+    CannotTuneDice,
+}
+
+impl GameEnvironment {
+    // Checks whether a client event can be processed in the current state
+    pub fn validate(&self, msg: &GameEvent) -> Result<(), RejectReason> {
+        if msg.is_connection_event() {
+            return Ok(());
+        }
+
+        if self.result.is_some() {
+            return Err(RejectReason::GameOver);
+        }
+
+        let waiting_for_choice = self.players.iter().any(|a| a.choosing_active);
+        if waiting_for_choice && !matches!(msg, GameEvent::ChooseActive(..) | GameEvent::SetupClient(_) | GameEvent::Concede(_)) {
+            return Err(RejectReason::WaitingForActiveCharacter);
+        }
+
+        if msg.is_combat_action() || msg.is_fast_action() {
+            let id = msg.player().unwrap();
+            if self.phase != Phase::Action {
+                return Err(RejectReason::WrongPhase);
+            }
+
+            if self.current_turn != id {
+                return Err(RejectReason::NotYourTurn);
+            }
+
+            if self.declared_end[id] {
+                return Err(RejectReason::AlreadyDeclaredEnd);
+            }
+        }
+
+        match msg {
+            GameEvent::RerollDice(id, dices) => {
+                if self.phase != Phase::Reroll {
+                    return Err(RejectReason::WrongPhase);
+                }
+
+                if self.players[*id].reroll_chances == 0 {
+                    return Err(RejectReason::NoRerollsLeft);
+                }
+
+                self.validate_dice_indices(*id, dices)
+            }
+
+            GameEvent::UseSkill(id, skill, dices) => {
+                let player = &self.players[*id];
+                let character = &player.characters[player.active_character];
+                if character.is_frozen() {
+                    return Err(RejectReason::CharacterFrozen);
+                }
+
+                let (cost, _) = self.skill_cost(*id, *skill);
+                if character.energy < cost.energy {
+                    return Err(RejectReason::InsufficientEnergy);
+                }
+
+                self.validate_payment(*id, dices, &cost)
+            }
+
+            GameEvent::UseActionCard(id, card, target, dices) => {
+                let player = &self.players[*id];
+                if *card >= player.hand.len() {
+                    return Err(RejectReason::InvalidCard);
+                }
+
+                if *target >= player.characters.len() {
+                    return Err(RejectReason::InvalidCharacter);
+                }
+
+                let info = OperationContext::new(*id, *target, self.players[1 - id].active_character);
+                if !player.hand[*card].can_use(&info, self) {
+                    return Err(RejectReason::CardConditionNotMet);
+                }
+
+                let (cost, _) = self.card_cost(*id, *card);
+                self.validate_payment(*id, dices, &cost)
+            }
+
+            GameEvent::Tune(id, card, dice) => {
+                let player = &self.players[*id];
+                if *card >= player.hand.len() {
+                    return Err(RejectReason::InvalidCard);
+                }
+
+                self.validate_dice_indices(*id, &[*dice])?;
+
+                let element = player.characters[player.active_character].element;
+                if player.dice_set.matches(*dice, element) {
+                    return Err(RejectReason::CannotTuneDice);
+                }
+
+                Ok(())
+            }
+
+            GameEvent::SwapCards(id, cards) => {
+                if self.phase != Phase::Mulligan {
+                    return Err(RejectReason::WrongPhase);
+                }
+
+                if self.mulligan_done[*id] {
+                    return Err(RejectReason::AlreadySwappedCards);
+                }
+
+                let unique: HashSet<&usize> = cards.iter().collect();
+                if unique.len() != cards.len() || cards.iter().any(|i| *i >= self.players[*id].hand.len()) {
+                    return Err(RejectReason::InvalidCard);
+                }
+
+                Ok(())
+            }
+
+            GameEvent::ChangeActive(id, t, _) | GameEvent::ChooseActive(id, t) => {
+                let player = &self.players[*id];
+                if matches!(msg, GameEvent::ChooseActive(..)) && !player.choosing_active {
+                    return Err(RejectReason::WrongPhase);
+                }
+
+                if *t >= player.characters.len() || *t == player.active_character {
+                    return Err(RejectReason::InvalidCharacter);
+                }
+
+                if player.characters[*t].defeated {
+                    return Err(RejectReason::CharacterDefeated);
+                }
+
+                match msg {
+                    GameEvent::ChangeActive(_, _, dices) => self.validate_payment(*id, dices, &self.switch_cost(*id).0),
+                    _ => Ok(()),
+                }
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_payment(&self, id: usize, dices: &[usize], cost: &Cost) -> Result<(), RejectReason> {
+        self.validate_dice_indices(id, dices)?;
+
+        if dices.len() != cost.dice_count() {
+            return Err(RejectReason::DiceCountMismatch);
+        }
+
+        if !self.players[id].dice_set.check_cost(dices, cost) {
+            return Err(RejectReason::DiceElementMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn validate_dice_indices(&self, id: usize, dices: &[usize]) -> Result<(), RejectReason> {
+        let dice_count = self.players[id].dice_set.dice_count;
+        let unique: HashSet<&usize> = dices.iter().collect();
+
+        if unique.len() != dices.len() || dices.iter().any(|i| *i >= dice_count) {
+            return Err(RejectReason::InvalidDice);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::dice_set::{DiceSet, ElementType};
+    use crate::game_environment::tests::{action_phase, new_game, omni_dice, run};
+    use crate::game_events::SkillType;
+    use crate::reactions::Frozen;
+
+    #[test]
+    fn rejects_actions_out_of_turn_and_phase() {
+        let env = new_game(1);
+        assert_eq!(env.validate(&GameEvent::UseSkill(0, SkillType::NormalAttack, vec![0, 1, 2])), Err(RejectReason::WrongPhase));
+        assert_eq!(env.validate(&GameEvent::RerollDice(0, vec![])), Err(RejectReason::WrongPhase));
+
+        let env = action_phase(1);
+        let id = env.current_turn;
+        assert_eq!(env.validate(&GameEvent::DeclareRoundEnd(1 - id)), Err(RejectReason::NotYourTurn));
+        assert_eq!(env.validate(&GameEvent::SwapCards(id, vec![])), Err(RejectReason::WrongPhase));
+        assert_eq!(env.validate(&GameEvent::DeclareRoundEnd(id)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_invalid_dice_indices() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+
+        let skill = |dices: Vec<usize>| GameEvent::UseSkill(id, SkillType::NormalAttack, dices);
+        assert_eq!(env.validate(&skill(vec![0, 1, 8])), Err(RejectReason::InvalidDice));
+        assert_eq!(env.validate(&skill(vec![0, 0, 1])), Err(RejectReason::InvalidDice));
+        assert_eq!(env.validate(&skill(vec![0, 1])), Err(RejectReason::DiceCountMismatch));
+        assert_eq!(env.validate(&skill(vec![0, 1, 2, 3])), Err(RejectReason::DiceCountMismatch));
+        assert_eq!(env.validate(&skill(vec![0, 1, 2])), Ok(()));
+    }
+
+    #[test]
+    fn rejects_dice_not_matching_the_cost() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        let player = &mut env.players[id];
+        let element = player.characters[player.active_character].element;
+        let other = if element == ElementType::Anemo { ElementType::Geo } else { ElementType::Anemo };
+
+        player.dice_set.dice_count = 0;
+        for _ in 0..3 {
+            player.dice_set.add_dice(other);
+        }
+        assert_eq!(env.validate(&GameEvent::UseSkill(id, SkillType::NormalAttack, vec![0, 1, 2])), Err(RejectReason::DiceElementMismatch));
+    }
+
+    #[test]
+    fn rejects_bursts_without_energy() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+
+        let player = &mut env.players[id];
+        let character = &mut player.characters[player.active_character];
+        character.energy = character.max_energy - 1;
+        let (cost, _) = env.skill_cost(id, SkillType::QSkill);
+        let dices = (0..cost.dice_count()).collect();
+        let burst = GameEvent::UseSkill(id, SkillType::QSkill, dices);
+        assert_eq!(env.validate(&burst), Err(RejectReason::InsufficientEnergy));
+
+        let player = &mut env.players[id];
+        player.characters[player.active_character].energy += 1;
+        assert_eq!(env.validate(&burst), Ok(()));
+    }
+
+    #[test]
+    fn rejects_skills_of_frozen_characters() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+
+        let player = &mut env.players[id];
+        player.characters[player.active_character].add_status(Arc::new(Frozen::new()));
+        assert_eq!(env.validate(&GameEvent::UseSkill(id, SkillType::NormalAttack, vec![0, 1, 2])), Err(RejectReason::CharacterFrozen));
+    }
+
+    #[test]
+    fn rejects_cards_out_of_range() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        omni_dice(&mut env, id);
+        let hand = env.players[id].hand.len();
+
+        assert_eq!(env.validate(&GameEvent::UseActionCard(id, hand, 0, vec![])), Err(RejectReason::InvalidCard));
+        assert_eq!(env.validate(&GameEvent::Tune(id, hand, 0)), Err(RejectReason::InvalidCard));
+        assert_eq!(env.validate(&GameEvent::UseActionCard(id, 0, 3, vec![])), Err(RejectReason::InvalidCharacter));
+    }
+
+    #[test]
+    fn rejects_tuning_omni_or_matching_dice() {
+        let mut env = action_phase(1);
+        let id = env.current_turn;
+        let player = &mut env.players[id];
+        let element = player.characters[player.active_character].element;
+        let other = if element == ElementType::Anemo { ElementType::Geo } else { ElementType::Anemo };
+
+        player.dice_set = DiceSet::default();
+        for die in [ElementType::Universal, element, other] {
+            player.dice_set.add_dice(die);
+        }
+        assert_eq!(env.validate(&GameEvent::Tune(id, 0, 0)), Err(RejectReason::CannotTuneDice));
+        assert_eq!(env.validate(&GameEvent::Tune(id, 0, 1)), Err(RejectReason::CannotTuneDice));
+        assert_eq!(env.validate(&GameEvent::Tune(id, 0, 3)), Err(RejectReason::InvalidDice));
+
+        let hand = env.players[id].hand.len();
+        run(&mut env, GameEvent::Tune(id, 0, 2));
+        let player = &env.players[id];
+        assert_eq!(player.hand.len(), hand - 1);
+        assert_eq!(player.dice_set.dices[0..3], [ElementType::Universal, element, element]);
+        // Tuning is a fast action
+        assert_eq!(env.current_turn, id);
+    }
+}